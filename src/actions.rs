@@ -0,0 +1,135 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+// What the player can do, independent of which key or button does it.
+// Gameplay systems read these from `ActionState` instead of raw input.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PlayerAction {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    // Also rises while flying
+    Jump,
+    // Sinks while flying
+    Descend,
+    Sprint,
+    Break,
+    Place,
+    // Cycles walking, flying and flying with collisions
+    ToggleFly,
+    // Switches between first- and third-person view
+    ToggleCamera,
+}
+
+impl PlayerAction {
+    pub const ALL: [PlayerAction; 11] = [
+        PlayerAction::MoveForward,
+        PlayerAction::MoveBack,
+        PlayerAction::MoveLeft,
+        PlayerAction::MoveRight,
+        PlayerAction::Jump,
+        PlayerAction::Descend,
+        PlayerAction::Sprint,
+        PlayerAction::Break,
+        PlayerAction::Place,
+        PlayerAction::ToggleFly,
+        PlayerAction::ToggleCamera,
+    ];
+}
+
+// A physical input that can trigger an action
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    fn pressed(self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match self {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse.pressed(button),
+        }
+    }
+}
+
+// Every binding of every action; an action may have several, and it is held
+// while any of them is
+#[derive(Resource, Clone, Debug)]
+pub struct ActionBindings {
+    pub bindings: Vec<(PlayerAction, Binding)>,
+}
+
+impl Default for ActionBindings {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (PlayerAction::MoveForward, Binding::Key(KeyCode::W)),
+                (PlayerAction::MoveBack, Binding::Key(KeyCode::S)),
+                (PlayerAction::MoveLeft, Binding::Key(KeyCode::A)),
+                (PlayerAction::MoveRight, Binding::Key(KeyCode::D)),
+                (PlayerAction::Jump, Binding::Key(KeyCode::Space)),
+                (PlayerAction::Descend, Binding::Key(KeyCode::ShiftLeft)),
+                (PlayerAction::Sprint, Binding::Key(KeyCode::ControlLeft)),
+                (PlayerAction::Break, Binding::Mouse(MouseButton::Left)),
+                (PlayerAction::Place, Binding::Mouse(MouseButton::Right)),
+                (PlayerAction::ToggleFly, Binding::Key(KeyCode::F)),
+                (PlayerAction::ToggleCamera, Binding::Key(KeyCode::F2)),
+            ],
+        }
+    }
+}
+
+// The actions held this frame, and which of them started this frame
+#[derive(Resource, Default, Debug)]
+pub struct ActionState {
+    pressed: HashSet<PlayerAction>,
+    just_pressed: HashSet<PlayerAction>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: PlayerAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: PlayerAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    // Holds or releases an action, e.g. from another input device or a test
+    pub fn set(&mut self, action: PlayerAction, pressed: bool) {
+        if !pressed {
+            self.pressed.remove(&action);
+            self.just_pressed.remove(&action);
+        } else if self.pressed.insert(action) {
+            self.just_pressed.insert(action);
+        }
+    }
+}
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionBindings>()
+            .init_resource::<ActionState>()
+            .add_systems(PreUpdate, gather_actions.after(InputSystem));
+    }
+}
+
+// Turns this frame's keys and buttons into actions through the bindings
+fn gather_actions(
+    bindings: Res<ActionBindings>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mut actions: ResMut<ActionState>,
+) {
+    actions.just_pressed.clear();
+    for action in PlayerAction::ALL {
+        let mut sources = bindings.bindings.iter().filter(|(bound, _)| *bound == action);
+        let pressed = sources.any(|&(_, binding)| binding.pressed(&keys, &mouse));
+        actions.set(action, pressed);
+    }
+}
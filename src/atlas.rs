@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy::render::texture::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+
+use crate::config::MAX_ANISOTROPY;
+use crate::settings::{Settings, TextureFilter};
+
+// Fewest texels along a tile's side a mip level keeps; smaller levels are
+// never sampled, since by then each texel blends neighbouring tiles together
+const MIN_MIP_TILE_TEXELS: u32 = 4;
+
+// Applies `Settings::atlas_filter` and `atlas_anisotropy` to the block atlas
+// whenever either changes or the atlas image (re)loads
+pub struct AtlasPlugin;
+
+impl Plugin for AtlasPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_atlas_sampler);
+    }
+}
+
+// The image holding every `AtlasTile`, square tiles `tile_size` texels wide.
+// Whatever loads the atlas inserts this; until then nothing samples one.
+#[derive(Resource)]
+pub struct BlockAtlas {
+    pub image: Handle<Image>,
+    pub tile_size: u32,
+}
+
+// Sampler for an atlas of `tile_size`-texel tiles. Nearest keeps the pixel
+// art crisp; the GPU only takes anisotropic samples with every filter linear,
+// so the degree is ignored otherwise. Mips stop while tiles are still
+// `MIN_MIP_TILE_TEXELS` wide, which keeps tiles from bleeding into each other
+// without padding the atlas.
+pub fn atlas_sampler(settings: &Settings, tile_size: u32) -> ImageSamplerDescriptor {
+    let (filter, anisotropy) = match settings.atlas_filter {
+        TextureFilter::Nearest => (ImageFilterMode::Nearest, 1),
+        TextureFilter::Linear => (ImageFilterMode::Linear, settings.atlas_anisotropy.clamp(1, MAX_ANISOTROPY)),
+    };
+    let levels = tile_size.max(1).ilog2().saturating_sub(MIN_MIP_TILE_TEXELS.ilog2());
+    ImageSamplerDescriptor {
+        label: Some("block_atlas".into()),
+        // The shader wraps UVs into each tile, so nothing should wrap past the atlas edge
+        address_mode_u: ImageAddressMode::ClampToEdge,
+        address_mode_v: ImageAddressMode::ClampToEdge,
+        address_mode_w: ImageAddressMode::ClampToEdge,
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_filter: filter,
+        lod_max_clamp: levels as f32,
+        anisotropy_clamp: anisotropy,
+        ..default()
+    }
+}
+
+fn apply_atlas_sampler(
+    settings: Res<Settings>,
+    atlas: Option<Res<BlockAtlas>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(atlas) = atlas else {
+        return;
+    };
+    // Not on `Modified`, which setting the sampler itself sends
+    let loaded = image_events
+        .read()
+        .any(|event| event.is_added(&atlas.image) || event.is_loaded_with_dependencies(&atlas.image));
+    if !(loaded || settings.is_changed() || atlas.is_changed()) {
+        return;
+    }
+    if let Some(image) = images.get_mut(&atlas.image) {
+        image.sampler = ImageSampler::Descriptor(atlas_sampler(&settings, atlas.tile_size));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_atlas_sampling_never_goes_anisotropic() {
+        let settings = Settings { atlas_filter: TextureFilter::Nearest, atlas_anisotropy: 8, ..default() };
+        let sampler = atlas_sampler(&settings, 16);
+        assert!(matches!(sampler.min_filter, ImageFilterMode::Nearest));
+        assert!(matches!(sampler.mipmap_filter, ImageFilterMode::Nearest));
+        assert_eq!(sampler.anisotropy_clamp, 1);
+
+        let settings = Settings { atlas_filter: TextureFilter::Linear, ..settings };
+        let sampler = atlas_sampler(&settings, 16);
+        assert!(matches!(sampler.mag_filter, ImageFilterMode::Linear));
+        assert_eq!(sampler.anisotropy_clamp, 8);
+    }
+
+    // 16-texel tiles keep mips 0 to 2, down to 4 texels; tiles that small
+    // to begin with get no mips at all
+    #[test]
+    fn atlas_mips_stop_before_tiles_blend() {
+        let settings = Settings::default();
+        assert_eq!(atlas_sampler(&settings, 16).lod_max_clamp, 2.0);
+        assert_eq!(atlas_sampler(&settings, 64).lod_max_clamp, 4.0);
+        assert_eq!(atlas_sampler(&settings, 4).lod_max_clamp, 0.0);
+        assert_eq!(atlas_sampler(&settings, 1).lod_max_clamp, 0.0);
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::head_bob::{apply_head_bob, remove_head_bob};
+use crate::player::{EYE_HEIGHT, PLAYER_HALF_WIDTH, PLAYER_HEIGHT};
+use crate::settings::Settings;
+use crate::world::raycast_render;
+use crate::{ActionState, GameState, Player, PlayerAction, PlayerPhysics, WorldMap, WorldOrigin};
+
+// Gap kept between the third-person camera and a block behind it, in render
+// units, so the near plane doesn't cut into the block
+const CAMERA_MARGIN: f32 = 0.2;
+const BODY_COLOR: Color = Color::rgb(0.25, 0.35, 0.6);
+
+// Like the head bob, the third-person pull-back only lives in the camera
+// transform between the end of `Update` and `Last`, so movement, raycasts and
+// saving all see the player's eye. The camera backs off along the view
+// direction, so the crosshair still marks what the eye looks at.
+pub struct CameraModePlugin;
+
+impl Plugin for CameraModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_player_body)
+            .add_systems(Update, toggle_camera_mode.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                PostUpdate,
+                (
+                    // The body stands on the steady feet, not the bobbing eye
+                    follow_player_body.before(apply_head_bob),
+                    pull_camera_back.after(apply_head_bob),
+                )
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(Last, remove_camera_pull_back.before(remove_head_bob));
+    }
+}
+
+// Where the camera sits; first person is the default
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CameraMode {
+    #[default]
+    FirstPerson,
+    // Behind the player, `Settings::third_person_distance` back
+    ThirdPerson,
+}
+
+// What `pull_camera_back` added to the camera transform this frame
+#[derive(Component, Default)]
+pub struct CameraPullBack(Vec3);
+
+// A plain box drawn where the player stands, seen only from third person
+#[derive(Component)]
+struct PlayerBody;
+
+fn spawn_player_body(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = Vec3::new(PLAYER_HALF_WIDTH * 2.0, PLAYER_HEIGHT, PLAYER_HALF_WIDTH * 2.0);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(shape::Box::new(size.x, size.y, size.z).into()),
+            material: materials.add(BODY_COLOR.into()),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PlayerBody,
+    ));
+}
+
+fn toggle_camera_mode(actions: Res<ActionState>, mut query: Query<&mut CameraMode, With<Player>>) {
+    if !actions.just_pressed(PlayerAction::ToggleCamera) {
+        return;
+    }
+    for mut mode in query.iter_mut() {
+        *mode = match *mode {
+            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+        };
+    }
+}
+
+// Render units the camera can back away from `eye` along `back` before it
+// would come within `CAMERA_MARGIN` of a block
+fn clear_distance(world_map: &WorldMap, world_origin: &WorldOrigin, eye: Vec3, back: Vec3, distance: f32) -> f32 {
+    match raycast_render(world_map, world_origin, eye, back, distance + CAMERA_MARGIN) {
+        Some(hit) => (hit.distance - CAMERA_MARGIN).clamp(0.0, distance),
+        None => distance,
+    }
+}
+
+fn pull_camera_back(
+    settings: Res<Settings>,
+    world_map: Res<WorldMap>,
+    world_origin: Res<WorldOrigin>,
+    mut query: Query<(&mut Transform, &CameraMode, &mut CameraPullBack), With<Player>>,
+) {
+    for (mut transform, &mode, mut pull_back) in query.iter_mut() {
+        pull_back.0 = match mode {
+            CameraMode::FirstPerson => Vec3::ZERO,
+            CameraMode::ThirdPerson => {
+                let back = transform.back();
+                let distance = settings.third_person_distance.max(0.0);
+                back * clear_distance(&world_map, &world_origin, transform.translation, back, distance)
+            }
+        };
+        transform.translation += pull_back.0;
+    }
+}
+
+pub fn remove_camera_pull_back(mut query: Query<(&mut Transform, &CameraPullBack)>) {
+    for (mut transform, pull_back) in query.iter_mut() {
+        transform.translation -= pull_back.0;
+    }
+}
+
+// Never the player, so both transforms can be borrowed at once
+type BodyOnly = (With<PlayerBody>, Without<Player>);
+
+// Stands the body under the eye, turned with the view but kept upright
+fn follow_player_body(
+    world_origin: Res<WorldOrigin>,
+    player_query: Query<(&Transform, &CameraMode, &PlayerPhysics), With<Player>>,
+    mut body_query: Query<(&mut Transform, &mut Visibility), BodyOnly>,
+) {
+    let Ok((eye, &mode, physics)) = player_query.get_single() else {
+        return;
+    };
+    for (mut body, mut visibility) in body_query.iter_mut() {
+        *visibility = match mode {
+            CameraMode::FirstPerson => Visibility::Hidden,
+            CameraMode::ThirdPerson => Visibility::Visible,
+        };
+        let scale = world_origin.voxel_scale;
+        body.translation = eye.translation + physics.up * (PLAYER_HEIGHT / 2.0 - EYE_HEIGHT) * scale;
+        body.scale = Vec3::splat(scale);
+        // Looking straight up or down keeps the last heading
+        let heading = eye.forward().reject_from(physics.up);
+        if heading.length_squared() > f32::EPSILON {
+            body.look_to(heading, physics.up);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockType, ChunkData};
+
+    // A wall three blocks behind an eye looking along +z keeps the camera
+    // off it; with nothing behind, it goes the whole way
+    #[test]
+    fn third_person_camera_stops_short_of_blocks() {
+        let mut chunk = ChunkData::new();
+        for x in 0..8 {
+            for y in 0..8 {
+                chunk.set(IVec3::new(x, y, 2), BlockType::Stone);
+            }
+        }
+        let mut world_map = WorldMap::default();
+        world_map.chunks.insert(IVec3::ZERO, chunk);
+        let origin = WorldOrigin { chunk: IVec3::ZERO, voxel_scale: 1.0 };
+
+        let eye = Vec3::new(4.5, 4.5, 6.0);
+        let clear = clear_distance(&world_map, &origin, eye, Vec3::NEG_Z, 4.0);
+        assert!((clear - (3.0 - CAMERA_MARGIN)).abs() < 1e-5, "camera backed off {clear}");
+        assert_eq!(clear_distance(&world_map, &origin, eye, Vec3::Z, 4.0), 4.0);
+        // An eye inside a block leaves the camera there
+        assert_eq!(clear_distance(&world_map, &origin, Vec3::new(4.5, 4.5, 2.5), Vec3::NEG_Z, 4.0), 0.0);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{MeshStyle, Settings, TextureFilter};
+use crate::GameState;
+
+// Loading further than this is more than the chunk pipeline keeps up with
+pub const MAX_RENDER_DISTANCE: i32 = 32;
+// Most samples the GPU takes across a glancing texel
+pub const MAX_ANISOTROPY: u16 = 16;
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConfigFile>()
+            // Before anything reads the settings at startup
+            .add_systems(PreStartup, load_config)
+            // Leaving the pause menu is when tweaks are done
+            .add_systems(OnExit(GameState::Paused), save_config)
+            .add_systems(Last, save_config_on_exit);
+    }
+}
+
+// Settings meant to be tweaked by the player, persisted between runs apart
+// from any world. Missing fields keep their defaults, so older files load.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct UserConfig {
+    pub render_distance: i32,
+    pub lod_distance: i32,
+    pub fov: f32,
+    pub mouse_sensitivity: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    pub raw_mouse_input: bool,
+    pub mesh_style: MeshStyle,
+    pub msaa_samples: u32,
+    pub atlas_filter: TextureFilter,
+    pub atlas_anisotropy: u16,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self::from_settings(&Settings::default())
+    }
+}
+
+impl UserConfig {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            render_distance: settings.render_distance,
+            lod_distance: settings.lod_distance,
+            fov: settings.fov,
+            mouse_sensitivity: settings.mouse_sensitivity,
+            invert_x: settings.invert_x,
+            invert_y: settings.invert_y,
+            raw_mouse_input: settings.raw_mouse_input,
+            mesh_style: settings.mesh_style,
+            msaa_samples: settings.msaa_samples,
+            atlas_filter: settings.atlas_filter,
+            atlas_anisotropy: settings.atlas_anisotropy,
+        }
+    }
+
+    // Pulls hand-edited values back into a playable range
+    pub fn validated(self) -> Self {
+        Self {
+            render_distance: self.render_distance.clamp(1, MAX_RENDER_DISTANCE),
+            lod_distance: self.lod_distance.max(1),
+            fov: if self.fov.is_finite() { self.fov.clamp(30.0, 120.0) } else { Settings::default().fov },
+            mouse_sensitivity: if self.mouse_sensitivity.is_finite() {
+                self.mouse_sensitivity.clamp(0.0001, 0.05)
+            } else {
+                Settings::default().mouse_sensitivity
+            },
+            msaa_samples: self.msaa_samples.clamp(1, 8),
+            atlas_anisotropy: self.atlas_anisotropy.clamp(1, MAX_ANISOTROPY),
+            ..self
+        }
+    }
+
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.render_distance = self.render_distance;
+        settings.lod_distance = self.lod_distance;
+        settings.fov = self.fov;
+        settings.mouse_sensitivity = self.mouse_sensitivity;
+        settings.invert_x = self.invert_x;
+        settings.invert_y = self.invert_y;
+        settings.raw_mouse_input = self.raw_mouse_input;
+        settings.mesh_style = self.mesh_style;
+        settings.msaa_samples = self.msaa_samples;
+        settings.atlas_filter = self.atlas_filter;
+        settings.atlas_anisotropy = self.atlas_anisotropy;
+    }
+}
+
+#[derive(Resource)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    // What is on disk, so unchanged settings are never rewritten
+    saved: Option<UserConfig>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("settings.ron"),
+            saved: None,
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn load(&self) -> Option<UserConfig> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => ron::from_str(&text)
+                .map_err(|err| warn!("Ignoring settings file {}: {err}", self.path.display()))
+                .ok(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Could not read settings file {}: {err}", self.path.display());
+                None
+            }
+        }
+    }
+
+    // Writes the config unless the file already holds it
+    pub fn save(&mut self, config: &UserConfig) -> io::Result<()> {
+        if self.saved.as_ref() == Some(config) {
+            return Ok(());
+        }
+        let text = ron::ser::to_string_pretty(config, ron::ser::PrettyConfig::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(&self.path, text)?;
+        self.saved = Some(config.clone());
+        Ok(())
+    }
+}
+
+fn load_config(mut config_file: ResMut<ConfigFile>, mut settings: ResMut<Settings>) {
+    let Some(config) = config_file.load() else {
+        return;
+    };
+    let config = config.validated();
+    config.apply(&mut settings);
+    config_file.saved = Some(config);
+}
+
+fn save_config(mut config_file: ResMut<ConfigFile>, settings: Res<Settings>) {
+    if let Err(err) = config_file.save(&UserConfig::from_settings(&settings)) {
+        error!("Failed to save settings to {}: {err}", config_file.path.display());
+    }
+}
+
+fn save_config_on_exit(exit_events: EventReader<AppExit>, config_file: ResMut<ConfigFile>, settings: Res<Settings>) {
+    if !exit_events.is_empty() {
+        save_config(config_file, settings);
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+
+use crate::interaction::TargetedBlock;
+use crate::settings::Settings;
+use crate::{
+    chunk_to_world_origin, climate_at, ChunkIndex, ChunkMeshStats, ChunkTasks, Player, WorldGen, WorldOrigin,
+    CHUNK_SIZE,
+};
+
+const MINIMAP_RADIUS: i32 = 8;
+const MINIMAP_CELL_SIZE: f32 = 6.0;
+const MINIMAP_LOADED_COLOR: Color = Color::rgb(0.3, 0.7, 0.3);
+const MINIMAP_GENERATING_COLOR: Color = Color::rgb(0.8, 0.6, 0.2);
+const MINIMAP_EMPTY_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const MINIMAP_OUT_OF_RANGE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.3);
+const CHUNK_BORDER_COLOR: Color = Color::rgb(0.9, 0.9, 0.2);
+const MODIFIED_CHUNK_BORDER_COLOR: Color = Color::rgb(0.9, 0.2, 0.9);
+
+pub struct DebugHudPlugin;
+
+impl Plugin for DebugHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugHud>()
+            .add_systems(Startup, setup_debug_hud)
+            .add_systems(Update, (toggle_debug_hud, update_debug_text, update_minimap, draw_chunk_borders));
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct DebugHud {
+    pub visible: bool,
+    pub chunk_borders: bool,
+}
+
+#[derive(Component)]
+struct DebugHudRoot;
+
+#[derive(Component)]
+struct DebugText;
+
+#[derive(Component)]
+struct MinimapCell {
+    offset: IVec2,
+}
+
+#[derive(Component)]
+struct MinimapFacing;
+
+fn setup_debug_hud(mut commands: Commands) {
+    let cells = 2 * MINIMAP_RADIUS + 1;
+    let minimap_size = cells as f32 * MINIMAP_CELL_SIZE;
+    let center = MINIMAP_RADIUS as f32 * MINIMAP_CELL_SIZE;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(8.0),
+                    top: Val::Px(8.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            DebugHudRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                DebugText,
+            ));
+
+            // Minimap: one cell per chunk column, centered on the player
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(minimap_size),
+                        height: Val::Px(minimap_size),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|minimap| {
+                    for x in -MINIMAP_RADIUS..=MINIMAP_RADIUS {
+                        for z in -MINIMAP_RADIUS..=MINIMAP_RADIUS {
+                            minimap.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        position_type: PositionType::Absolute,
+                                        left: Val::Px((x + MINIMAP_RADIUS) as f32 * MINIMAP_CELL_SIZE),
+                                        top: Val::Px((z + MINIMAP_RADIUS) as f32 * MINIMAP_CELL_SIZE),
+                                        width: Val::Px(MINIMAP_CELL_SIZE - 1.0),
+                                        height: Val::Px(MINIMAP_CELL_SIZE - 1.0),
+                                        ..default()
+                                    },
+                                    background_color: MINIMAP_OUT_OF_RANGE_COLOR.into(),
+                                    ..default()
+                                },
+                                MinimapCell { offset: IVec2::new(x, z) },
+                            ));
+                        }
+                    }
+
+                    // Player marker
+                    minimap.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(center + 1.0),
+                            top: Val::Px(center + 1.0),
+                            width: Val::Px(MINIMAP_CELL_SIZE - 3.0),
+                            height: Val::Px(MINIMAP_CELL_SIZE - 3.0),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    });
+
+                    // Facing indicator, moved around the marker each frame
+                    minimap.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: Val::Px(3.0),
+                                height: Val::Px(3.0),
+                                ..default()
+                            },
+                            background_color: Color::RED.into(),
+                            ..default()
+                        },
+                        MinimapFacing,
+                    ));
+                });
+        });
+}
+
+fn toggle_debug_hud(
+    input: Res<Input<KeyCode>>,
+    mut debug_hud: ResMut<DebugHud>,
+    mut root_query: Query<&mut Visibility, With<DebugHudRoot>>,
+) {
+    if input.just_pressed(KeyCode::F3) {
+        debug_hud.visible = !debug_hud.visible;
+        for mut visibility in root_query.iter_mut() {
+            *visibility = if debug_hud.visible { Visibility::Visible } else { Visibility::Hidden };
+        }
+    }
+    if input.just_pressed(KeyCode::F4) {
+        debug_hud.chunk_borders = !debug_hud.chunk_borders;
+    }
+}
+
+fn draw_chunk_borders(
+    debug_hud: Res<DebugHud>,
+    chunk_index: Res<ChunkIndex>,
+    world_origin: Res<WorldOrigin>,
+    mut gizmos: Gizmos,
+) {
+    if !debug_hud.chunk_borders {
+        return;
+    }
+    let size = CHUNK_SIZE as f32 * world_origin.voxel_scale;
+
+    for (position, entry) in chunk_index.chunks.iter() {
+        let center = world_origin.to_render(chunk_to_world_origin(*position)) + Vec3::splat(size / 2.0);
+        let color = if entry.modified { MODIFIED_CHUNK_BORDER_COLOR } else { CHUNK_BORDER_COLOR };
+        gizmos.cuboid(Transform::from_translation(center).with_scale(Vec3::splat(size)), color);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_debug_text(
+    debug_hud: Res<DebugHud>,
+    settings: Res<Settings>,
+    chunk_index: Res<ChunkIndex>,
+    chunk_tasks: Res<ChunkTasks>,
+    target: Res<TargetedBlock>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    // Biome line for the block it was last worked out at
+    mut biome: Local<Option<(IVec3, String)>>,
+    player_query: Query<&Transform, With<Player>>,
+    stats_query: Query<&ChunkMeshStats>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    if !debug_hud.visible {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let position = world_origin.to_world(player_transform.translation);
+    let eye = player_transform.translation;
+    let target = match target.0 {
+        Some(hit) => format!("{:?} at {} {} {}", hit.block, hit.voxel.x, hit.voxel.y, hit.voxel.z),
+        None => "nothing".to_string(),
+    };
+
+    // Sampled the same way generation does, but only on entering a new block
+    let block = world_origin.voxel_at(eye);
+    if !biome.as_ref().is_some_and(|(at, _)| *at == block) {
+        let (temperature, humidity) = climate_at(&world_gen.temperature, &world_gen.humidity, block.x, block.z);
+        let name = world_gen.biome_at(block.x, block.z);
+        *biome = Some((block, format!("{name:?} (temperature {temperature:.2}, humidity {humidity:.2})")));
+    }
+    let biome_line = biome.as_ref().map_or("", |(_, line)| line.as_str());
+    // Edited since generation, so saved on unload and on the next save
+    let modified = chunk_index.chunks.values().filter(|entry| entry.modified).count();
+    let (vertices, indices) = stats_query.iter().fold((0, 0), |(vertices, indices), stats| {
+        (vertices + stats.vertices, indices + stats.indices)
+    });
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "XYZ: {:.1} / {:.1} / {:.1}\nBiome: {}\nChunks: {} ({} modified)\nMesh: {} vertices, {} triangles\n\
+             Generating: {} / {}\nTarget: {}",
+            position.x,
+            position.y,
+            position.z,
+            biome_line,
+            chunk_index.chunks.len(),
+            modified,
+            vertices,
+            indices / 3,
+            chunk_tasks.tasks.len(),
+            settings.max_concurrent_chunk_tasks,
+            target
+        );
+    }
+}
+
+fn update_minimap(
+    debug_hud: Res<DebugHud>,
+    settings: Res<Settings>,
+    chunk_index: Res<ChunkIndex>,
+    world_origin: Res<WorldOrigin>,
+    player_query: Query<&Transform, With<Player>>,
+    mut cell_query: Query<(&MinimapCell, &mut BackgroundColor)>,
+    mut facing_query: Query<&mut Style, With<MinimapFacing>>,
+) {
+    if !debug_hud.visible {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_chunk = world_origin.chunk_at(player_transform.translation);
+
+    for (cell, mut color) in cell_query.iter_mut() {
+        let chunk_pos = player_chunk + IVec3::new(cell.offset.x, 0, cell.offset.y);
+        *color = match chunk_index.chunks.get(&chunk_pos) {
+            Some(entry) if entry.empty => MINIMAP_EMPTY_COLOR,
+            Some(_) => MINIMAP_LOADED_COLOR,
+            None if cell.offset.abs().max_element() <= settings.render_distance => MINIMAP_GENERATING_COLOR,
+            None => MINIMAP_OUT_OF_RANGE_COLOR,
+        }
+        .into();
+    }
+
+    let forward = player_transform.forward();
+    let facing = Vec2::new(forward.x, forward.z).normalize_or_zero() * MINIMAP_CELL_SIZE * 1.5;
+    let center = MINIMAP_RADIUS as f32 * MINIMAP_CELL_SIZE + MINIMAP_CELL_SIZE / 2.0 - 1.5;
+    for mut style in facing_query.iter_mut() {
+        style.left = Val::Px(center + facing.x);
+        style.top = Val::Px(center + facing.y);
+    }
+}
@@ -14,9 +14,25 @@ struct Chunk {
     position: IVec3,
 }
 
+// Material shared by every chunk, so chunks can be batched together
+#[derive(Resource)]
+struct ChunkMaterials {
+    opaque: Handle<StandardMaterial>,
+}
+
+impl FromWorld for ChunkMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        Self {
+            opaque: materials.add(Color::rgb(0.5, 0.5, 0.5).into()),
+        }
+    }
+}
+
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
+        .init_resource::<ChunkMaterials>()
         .add_systems(Startup, setup)
         .add_systems(Update, (player_movement, generate_chunks))
         .run();
@@ -25,7 +41,7 @@ fn main() {
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    chunk_materials: Res<ChunkMaterials>,
 ) {
     // Player
     commands.spawn((
@@ -50,7 +66,7 @@ fn setup(
     // Generate initial chunks
     for x in -WORLD_SIZE..WORLD_SIZE {
         for z in -WORLD_SIZE..WORLD_SIZE {
-            spawn_chunk(&mut commands, &mut meshes, &mut materials, IVec3::new(x, 0, z));
+            spawn_chunk(&mut commands, &mut meshes, &chunk_materials, IVec3::new(x, 0, z));
         }
     }
 }
@@ -89,7 +105,7 @@ fn player_movement(
 fn generate_chunks(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    chunk_materials: Res<ChunkMaterials>,
     player_query: Query<&Transform, With<Player>>,
     chunk_query: Query<(Entity, &Chunk)>,
 ) {
@@ -108,7 +124,7 @@ fn generate_chunks(
         for z in -WORLD_SIZE..=WORLD_SIZE {
             let chunk_pos = player_chunk + IVec3::new(x, 0, z);
             if !chunk_query.iter().any(|(_, c)| c.position == chunk_pos) {
-                spawn_chunk(&mut commands, &mut meshes, &mut materials, chunk_pos);
+                spawn_chunk(&mut commands, &mut meshes, &chunk_materials, chunk_pos);
             }
         }
     }
@@ -117,7 +133,7 @@ fn generate_chunks(
 fn spawn_chunk(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
+    chunk_materials: &ChunkMaterials,
     position: IVec3,
 ) {
     let noise = Perlin::new(0);
@@ -145,7 +161,7 @@ fn spawn_chunk(
     commands.spawn((
         PbrBundle {
             mesh: meshes.add(mesh),
-            material: materials.add(Color::rgb(0.5, 0.5, 0.5).into()),
+            material: chunk_materials.opaque.clone(),
             transform: Transform::from_xyz(
                 (position.x * CHUNK_SIZE) as f32,
                 0.0,
@@ -0,0 +1,310 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::history::EditHistory;
+use crate::interaction::{Hotbar, PlayerSight};
+use crate::player::overlaps_player;
+use crate::{
+    edit_block, world_to_chunk, BlockChanged, BlockType, Player, PlayerPhysics, WorldGen, WorldMap, WorldOrigin,
+};
+
+// Largest box filled at once, in blocks, so a stray corner can't stall the game
+const MAX_FILL_VOLUME: i32 = 32 * 32 * 32;
+#[cfg(not(feature = "headless"))]
+const SELECTION_COLOR: Color = Color::rgb(0.2, 0.8, 0.9);
+
+// Box editing: with the tool out (B), clicks mark two corner voxels, then
+// Enter fills the box between them with the selected block and Backspace
+// clears it to air. Ctrl+C copies the box to the clipboard, R turns the
+// clipboard a quarter turn and Ctrl+V pastes it in front of the targeted
+// face. A fill or paste is a single undo step.
+#[derive(Resource, Default)]
+pub struct FillTool {
+    pub active: bool,
+    pub corners: [Option<IVec3>; 2],
+}
+
+impl FillTool {
+    // Inclusive bounds of the marked box, once both corners are set
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        let [Some(a), Some(b)] = self.corners else {
+            return None;
+        };
+        Some((a.min(b), a.max(b)))
+    }
+}
+
+// Voxels copied out of a box, indexed x first, then y, then z
+#[derive(Resource, Default)]
+pub struct VoxelClipboard {
+    pub size: IVec3,
+    pub blocks: Vec<BlockType>,
+}
+
+impl VoxelClipboard {
+    pub fn get(&self, offset: IVec3) -> BlockType {
+        self.blocks[box_index(self.size, offset)]
+    }
+
+    // A quarter turn clockwise seen from above
+    pub fn rotate(&mut self) {
+        let rotated_size = IVec3::new(self.size.z, self.size.y, self.size.x);
+        let mut blocks = vec![BlockType::Air; self.blocks.len()];
+        for offset in box_offsets(self.size) {
+            let turned = IVec3::new(self.size.z - 1 - offset.z, offset.y, offset.x);
+            // Directional blocks turn with the box
+            blocks[box_index(rotated_size, turned)] = match self.get(offset) {
+                BlockType::Log(facing) => BlockType::Log(facing.quarter_turn()),
+                block => block,
+            };
+        }
+        self.size = rotated_size;
+        self.blocks = blocks;
+    }
+}
+
+fn box_index(size: IVec3, offset: IVec3) -> usize {
+    (offset.x + size.x * (offset.y + size.y * offset.z)) as usize
+}
+
+// Whether a box of the given size is small enough to fill or copy. Worked
+// out in i64, since a box spanning the world can hold more than i32 blocks.
+fn within_fill_limit(size: IVec3) -> bool {
+    size.x as i64 * size.y as i64 * size.z as i64 <= MAX_FILL_VOLUME as i64
+}
+
+// Every offset inside a box of the given size, in clipboard order
+fn box_offsets(size: IVec3) -> impl Iterator<Item = IVec3> {
+    (0..size.z).flat_map(move |z| (0..size.y).flat_map(move |y| (0..size.x).map(move |x| IVec3::new(x, y, z))))
+}
+
+// Pasted blocks that landed in chunks not loaded yet, by chunk, written in
+// as soon as each chunk loads. They aren't part of the undo history.
+#[derive(Resource, Default)]
+pub struct PendingEdits {
+    chunks: HashMap<IVec3, Vec<(IVec3, BlockType)>>,
+}
+
+impl PendingEdits {
+    // Holds an edit of an unloaded voxel until its chunk loads
+    pub fn push(&mut self, world_pos: IVec3, block: BlockType) {
+        self.chunks.entry(world_to_chunk(world_pos)).or_default().push((world_pos, block));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+}
+
+pub fn toggle_fill_tool(input: Res<Input<KeyCode>>, mut tool: ResMut<FillTool>) {
+    if input.just_pressed(KeyCode::B) {
+        tool.active = !tool.active;
+        tool.corners = [None, None];
+    }
+}
+
+// Marks the targeted voxel, starting a new box once both corners are set
+pub fn mark_fill_corner(
+    mouse: Res<Input<MouseButton>>,
+    world_map: Res<WorldMap>,
+    mut tool: ResMut<FillTool>,
+    sight: PlayerSight,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(hit) = sight.target(&world_map) else {
+        return;
+    };
+    tool.corners = match tool.corners {
+        [Some(first), None] => [Some(first), Some(hit.voxel)],
+        _ => [Some(hit.voxel), None],
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn apply_fill(
+    input: Res<Input<KeyCode>>,
+    tool: Res<FillTool>,
+    hotbar: Res<Hotbar>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+    mut history: ResMut<EditHistory>,
+    player_query: Query<(&Transform, &PlayerPhysics), With<Player>>,
+) {
+    let block = if input.just_pressed(KeyCode::Return) {
+        hotbar.selected_block()
+    } else if input.just_pressed(KeyCode::Back) {
+        BlockType::Air
+    } else {
+        return;
+    };
+    let Some((min, max)) = tool.bounds() else {
+        return;
+    };
+    let size = max - min + IVec3::ONE;
+    if !within_fill_limit(size) {
+        warn!("A {}×{}×{} box is too big to fill (at most {MAX_FILL_VOLUME} blocks)", size.x, size.y, size.z);
+        return;
+    }
+
+    // Every block goes through `edit_block`; the remesh queue merges them, so
+    // each touched chunk is rebuilt once
+    let stance = player_query.get_single().ok().map(|(transform, physics)| (transform.translation, physics.up));
+    let overlaps = |voxel| stance.is_some_and(|(eye, up)| overlaps_player(eye, up, &world_origin, voxel));
+    let mut changes = Vec::new();
+    for x in min.x..=max.x {
+        for y in min.y.max(world_gen.bottom_y)..=max.y.min(world_gen.top_y) {
+            for z in min.z..=max.z {
+                let world_pos = IVec3::new(x, y, z);
+                let Some(old) = world_map.voxel(world_pos) else {
+                    continue;
+                };
+                let blocked = block.is_solid() && overlaps(world_pos);
+                // Bedrock stays, but air and water can be filled over
+                if old == block || old == BlockType::Bedrock || blocked {
+                    continue;
+                }
+                changes.extend(edit_block(&mut world_map, &mut block_events, world_pos, block));
+            }
+        }
+    }
+    history.record_group(changes);
+}
+
+pub fn copy_box(
+    input: Res<Input<KeyCode>>,
+    tool: Res<FillTool>,
+    world_gen: Res<WorldGen>,
+    world_map: Res<WorldMap>,
+    mut clipboard: ResMut<VoxelClipboard>,
+) {
+    if !ctrl_pressed(&input) || !input.just_pressed(KeyCode::C) {
+        return;
+    }
+    let Some((min, max)) = tool.bounds() else {
+        return;
+    };
+    let size = max - min + IVec3::ONE;
+    if !within_fill_limit(size) {
+        warn!("A {}×{}×{} box is too big to copy (at most {MAX_FILL_VOLUME} blocks)", size.x, size.y, size.z);
+        return;
+    }
+
+    clipboard.size = size;
+    // Unloaded voxels copy as they would generate
+    clipboard.blocks = box_offsets(size)
+        .map(|offset| {
+            let world_pos = min + offset;
+            world_map.voxel(world_pos).unwrap_or_else(|| world_gen.voxel_at(world_pos))
+        })
+        .collect();
+}
+
+pub fn rotate_clipboard(input: Res<Input<KeyCode>>, mut clipboard: ResMut<VoxelClipboard>) {
+    if input.just_pressed(KeyCode::R) {
+        clipboard.rotate();
+    }
+}
+
+// Pastes with the clipboard's low corner just outside the targeted face
+#[allow(clippy::too_many_arguments)]
+pub fn paste_clipboard(
+    input: Res<Input<KeyCode>>,
+    clipboard: Res<VoxelClipboard>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+    mut history: ResMut<EditHistory>,
+    mut pending: ResMut<PendingEdits>,
+    sight: PlayerSight,
+) {
+    if !ctrl_pressed(&input) || !input.just_pressed(KeyCode::V) || clipboard.blocks.is_empty() {
+        return;
+    }
+    let (Some(eye), Some(hit)) = (sight.eye(), sight.target(&world_map)) else {
+        return;
+    };
+
+    let corner = hit.voxel + hit.normal;
+    let mut changes = Vec::new();
+    for offset in box_offsets(clipboard.size) {
+        let world_pos = corner + offset;
+        let block = clipboard.get(offset);
+        let blocked = block.is_solid() && overlaps_player(eye, sight.up(), &world_origin, world_pos);
+        if !world_gen.in_bounds(world_pos.y) || blocked {
+            continue;
+        }
+        match world_map.voxel(world_pos) {
+            Some(old) if old == block || old == BlockType::Bedrock => {}
+            Some(_) => changes.extend(edit_block(&mut world_map, &mut block_events, world_pos, block)),
+            None => pending.push(world_pos, block),
+        }
+    }
+    history.record_group(changes);
+}
+
+// Writes pasted blocks into their chunks once those are loaded
+pub fn apply_pending_edits(
+    mut pending: ResMut<PendingEdits>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+) {
+    if pending.is_empty() {
+        return;
+    }
+    let loaded: Vec<IVec3> =
+        pending.chunks.keys().copied().filter(|&chunk_pos| world_map.contains(chunk_pos)).collect();
+    for chunk_pos in loaded {
+        for (world_pos, block) in pending.chunks.remove(&chunk_pos).unwrap_or_default() {
+            if world_map.voxel(world_pos).is_some_and(|old| old != block && old != BlockType::Bedrock) {
+                edit_block(&mut world_map, &mut block_events, world_pos, block);
+            }
+        }
+    }
+}
+
+fn ctrl_pressed(input: &Input<KeyCode>) -> bool {
+    input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+#[cfg(not(feature = "headless"))]
+pub fn draw_fill_selection(tool: Res<FillTool>, world_origin: Res<WorldOrigin>, mut gizmos: Gizmos) {
+    if !tool.active {
+        return;
+    }
+    // A lone first corner shows as a one-block box
+    let (min, max) = match tool.corners {
+        [Some(first), None] => (first, first),
+        _ => match tool.bounds() {
+            Some(bounds) => bounds,
+            None => return,
+        },
+    };
+    let scale = world_origin.voxel_scale;
+    let low = world_origin.to_render(min);
+    let high = world_origin.to_render(max + IVec3::ONE);
+    let center = (low + high) / 2.0;
+    let transform = Transform::from_translation(center).with_scale(high - low + Vec3::splat(0.02 * scale));
+    gizmos.cuboid(transform, SELECTION_COLOR);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_boxes_are_over_the_fill_limit() {
+        assert!(within_fill_limit(IVec3::splat(32)));
+        assert!(!within_fill_limit(IVec3::new(33, 32, 32)));
+        // 2^36 blocks, which wraps around in i32
+        assert!(!within_fill_limit(IVec3::splat(4096)));
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::{edit_block, queue_block_changes, BlockChanged, BlockType, GameState, WorldMap};
+
+const HORIZONTAL: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+pub struct FluidPlugin;
+
+impl Plugin for FluidPlugin {
+    fn build(&self, app: &mut App) {
+        // Spreading lands in the same frame's remesh as the edit that caused it
+        app.init_resource::<FluidSim>().add_systems(
+            Update,
+            (track_fluid_changes, simulate_fluids)
+                .chain()
+                .run_if(in_state(GameState::Playing))
+                .before(queue_block_changes),
+        );
+    }
+}
+
+// Water spreads on a fixed tick from cells near recent edits. Source blocks
+// never change; flowing water takes its level from its neighbors, so it
+// dries up again once cut off from a source.
+#[derive(Resource)]
+pub struct FluidSim {
+    // Blocks water can flow sideways from a source
+    pub max_distance: u8,
+    // Cells updated per tick; the rest wait for the next one
+    pub max_updates: usize,
+    pub tick: Timer,
+    pending: VecDeque<IVec3>,
+    queued: HashSet<IVec3>,
+}
+
+impl Default for FluidSim {
+    fn default() -> Self {
+        Self {
+            max_distance: 7,
+            max_updates: 256,
+            tick: Timer::from_seconds(0.25, TimerMode::Repeating),
+            pending: VecDeque::new(),
+            queued: HashSet::new(),
+        }
+    }
+}
+
+impl FluidSim {
+    // Forgets every cell waiting to flow, e.g. when the world is thrown away
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.queued.clear();
+    }
+
+    fn queue(&mut self, position: IVec3) {
+        if self.queued.insert(position) {
+            self.pending.push_back(position);
+        }
+    }
+
+    fn level(&self, block: BlockType) -> u8 {
+        match block {
+            BlockType::Water => self.max_distance + 1,
+            BlockType::FlowingWater(level) => level,
+            _ => 0,
+        }
+    }
+
+    // What an air or flowing water cell should hold given its surroundings,
+    // or `None` if any of them isn't loaded
+    fn settled_block(&self, world_map: &WorldMap, position: IVec3) -> Option<BlockType> {
+        let falling = world_map.voxel(position + IVec3::Y)?.is_water();
+        let mut level = if falling { self.max_distance } else { 0 };
+
+        for offset in HORIZONTAL {
+            let neighbor = position + offset;
+            let below = world_map.voxel(neighbor - IVec3::Y)?;
+            // Water over air falls instead of spreading
+            if below.is_solid() || below == BlockType::Water {
+                level = level.max(self.level(world_map.voxel(neighbor)?).saturating_sub(1));
+            }
+        }
+
+        Some(if level == 0 { BlockType::Air } else { BlockType::FlowingWater(level) })
+    }
+}
+
+fn track_fluid_changes(mut events: EventReader<BlockChanged>, mut sim: ResMut<FluidSim>) {
+    for event in events.read() {
+        sim.queue(event.world_pos);
+        for offset in HORIZONTAL.into_iter().chain([IVec3::Y, IVec3::NEG_Y]) {
+            sim.queue(event.world_pos + offset);
+        }
+    }
+}
+
+fn simulate_fluids(
+    time: Res<Time>,
+    mut sim: ResMut<FluidSim>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+) {
+    if !sim.tick.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // Changes made here are picked up by `track_fluid_changes` next frame,
+    // so each tick advances the flow by one block
+    for _ in 0..sim.max_updates {
+        let Some(position) = sim.pending.pop_front() else {
+            break;
+        };
+        sim.queued.remove(&position);
+
+        let Some(current) = world_map.voxel(position) else {
+            continue;
+        };
+        if !matches!(current, BlockType::Air | BlockType::FlowingWater(_)) {
+            continue;
+        }
+        if let Some(settled) = sim.settled_block(&world_map, position) {
+            if settled != current {
+                edit_block(&mut world_map, &mut block_events, position, settled);
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+use crate::player::WALK_SPEED;
+use crate::settings::Settings;
+use crate::{GameState, MovementMode, Player, PlayerPhysics};
+
+// Below this horizontal speed the player counts as standing still
+const MIN_BOB_SPEED: f32 = 0.1;
+// How quickly the bob fades in and out, per second
+const BOB_EASE: f32 = 8.0;
+
+// The bob only lives in the camera transform between the end of `Update` and
+// the end of the frame: it is added just before transforms propagate and
+// taken back off in `Last`. Movement, raycasts and saving all see the steady eye.
+pub struct HeadBobPlugin;
+
+impl Plugin for HeadBobPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, apply_head_bob.before(TransformSystem::TransformPropagate))
+            .add_systems(Last, remove_head_bob);
+    }
+}
+
+#[derive(Component, Default)]
+pub struct HeadBob {
+    // Position along the bob's sine wave, advanced by distance walked
+    phase: f32,
+    // Eased towards 1 at walking speed, 0 when idle, airborne or flying
+    weight: f32,
+    // What was added to the transform this frame
+    offset: Vec3,
+}
+
+pub fn apply_head_bob(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    mut query: Query<(&mut Transform, &mut HeadBob, &PlayerPhysics, &MovementMode), With<Player>>,
+) {
+    for (mut transform, mut bob, physics, &mode) in query.iter_mut() {
+        // Hold the last offset while paused rather than dropping the camera
+        if *state.get() == GameState::Playing {
+            let dt = time.delta_seconds();
+            let speed = physics.horizontal_velocity().length();
+            let walking = mode == MovementMode::Walk && physics.grounded && speed > MIN_BOB_SPEED;
+            let target = if settings.head_bob && walking { speed / WALK_SPEED } else { 0.0 };
+            bob.weight += (target - bob.weight) * (BOB_EASE * dt).min(1.0);
+            bob.phase = (bob.phase + speed * dt * settings.head_bob_frequency * TAU) % TAU;
+            bob.offset = physics.up * bob.phase.sin() * settings.head_bob_amplitude * bob.weight;
+        }
+        transform.translation += bob.offset;
+    }
+}
+
+pub fn remove_head_bob(mut query: Query<(&mut Transform, &HeadBob)>) {
+    for (mut transform, bob) in query.iter_mut() {
+        transform.translation -= bob.offset;
+    }
+}
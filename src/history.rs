@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::player::overlaps_player;
+use crate::{edit_block, BlockChanged, Player, PlayerPhysics, WorldMap, WorldOrigin};
+
+// Player edits kept for undo; the oldest fall off past this
+const UNDO_LIMIT: usize = 256;
+
+// The player's recent block placements and breaks. Undo (Ctrl+Z) puts the
+// old blocks back and redo (Ctrl+Y) repeats the edit, both through
+// `edit_block` so they remesh and save like any other change. Each entry is
+// one action, which may have changed many blocks at once.
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    undo: VecDeque<Vec<BlockChanged>>,
+    redo: Vec<Vec<BlockChanged>>,
+}
+
+impl EditHistory {
+    pub fn record(&mut self, change: BlockChanged) {
+        self.record_group(vec![change]);
+    }
+
+    // A new edit makes anything undone so far unreachable
+    pub fn record_group(&mut self, changes: Vec<BlockChanged>) {
+        if !changes.is_empty() {
+            self.push_undo(changes);
+            self.redo.clear();
+        }
+    }
+
+    fn push_undo(&mut self, changes: Vec<BlockChanged>) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(changes);
+    }
+}
+
+pub fn undo_redo(
+    input: Res<Input<KeyCode>>,
+    mut history: ResMut<EditHistory>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+    world_origin: Res<WorldOrigin>,
+    player_query: Query<(&Transform, &PlayerPhysics), With<Player>>,
+) {
+    if !input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let undo = input.just_pressed(KeyCode::Z);
+    if !undo && !input.just_pressed(KeyCode::Y) {
+        return;
+    }
+    let Some(mut changes) = (if undo { history.undo.pop_back() } else { history.redo.pop() }) else {
+        return;
+    };
+    let put_back = |history: &mut EditHistory, changes| {
+        if undo {
+            history.undo.push_back(changes);
+        } else {
+            history.redo.push(changes);
+        }
+    };
+    // Part of the action is in a chunk that has unloaded; keep it whole until
+    // the player is back, rather than undo half of it
+    if changes.iter().any(|change| world_map.voxel(change.world_pos).is_none()) {
+        info!("Can't {} that edit until its chunks load again", if undo { "undo" } else { "redo" });
+        put_back(&mut history, changes);
+        return;
+    }
+    // Undo walks the action backwards, so a block edited twice ends up as it started
+    if undo {
+        changes.reverse();
+    }
+
+    let stance = player_query.get_single().ok().map(|(transform, physics)| (transform.translation, physics.up));
+    let overlaps = |voxel| stance.is_some_and(|(eye, up)| overlaps_player(eye, up, &world_origin, voxel));
+    let mut applied = Vec::with_capacity(changes.len());
+    let mut blocked = false;
+    for &change in &changes {
+        let (expected, target) = if undo { (change.new, change.old) } else { (change.old, change.new) };
+        // Something else changed the block since, e.g. water flowing in; skip
+        // it rather than clobber it. One that would end up inside the player
+        // waits for them to step aside.
+        if world_map.voxel(change.world_pos) != Some(expected) {
+            continue;
+        }
+        if target.is_solid() && overlaps(change.world_pos) {
+            blocked = true;
+        } else if edit_block(&mut world_map, &mut block_events, change.world_pos, target).is_some() {
+            applied.push(change);
+        }
+    }
+
+    // Nothing happened, so the action stays where it was unless none of it
+    // can ever apply again
+    if applied.is_empty() {
+        if blocked {
+            if undo {
+                changes.reverse();
+            }
+            put_back(&mut history, changes);
+        }
+        return;
+    }
+    if undo {
+        applied.reverse();
+        history.redo.push(applied);
+    } else {
+        history.push_undo(applied);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{BlockType, ChunkData};
+
+    // Presses Ctrl and `key` for one run of `undo_redo`
+    fn press(world: &mut World, key: KeyCode) {
+        let mut input = Input::<KeyCode>::default();
+        input.press(KeyCode::ControlLeft);
+        input.press(key);
+        world.insert_resource(input);
+        world.run_system_once(undo_redo);
+    }
+
+    #[test]
+    fn edits_in_unloaded_chunks_wait_to_be_undone() {
+        let (near, far) = (IVec3::new(1, 1, 1), IVec3::new(40, 1, 1));
+        let mut world = World::new();
+        world.init_resource::<WorldMap>();
+        world.init_resource::<WorldOrigin>();
+        world.init_resource::<Events<BlockChanged>>();
+        world.init_resource::<EditHistory>();
+        let mut placed = ChunkData::new();
+        placed.set(near, BlockType::Stone);
+        world.resource_mut::<WorldMap>().chunks.insert(IVec3::ZERO, placed);
+        let change = |world_pos| BlockChanged { world_pos, old: BlockType::Air, new: BlockType::Stone };
+        world.resource_mut::<EditHistory>().record_group(vec![change(near), change(far)]);
+
+        // The far block's chunk is gone, so neither half of the action undoes
+        press(&mut world, KeyCode::Z);
+        assert_eq!(world.resource::<WorldMap>().voxel(near), Some(BlockType::Stone));
+        assert_eq!(world.resource::<EditHistory>().undo.len(), 1);
+
+        let mut far_chunk = ChunkData::new();
+        far_chunk.set(far - IVec3::new(32, 0, 0), BlockType::Stone);
+        world.resource_mut::<WorldMap>().chunks.insert(IVec3::new(2, 0, 0), far_chunk);
+        press(&mut world, KeyCode::Z);
+        let voxels = |world: &World| [near, far].map(|voxel| world.resource::<WorldMap>().voxel(voxel));
+        assert_eq!(voxels(&world), [Some(BlockType::Air); 2]);
+
+        // Redo waits the same way
+        world.resource_mut::<WorldMap>().chunks.remove(&IVec3::ZERO);
+        press(&mut world, KeyCode::Y);
+        assert_eq!(world.resource::<EditHistory>().redo.len(), 1);
+        world.resource_mut::<WorldMap>().chunks.insert(IVec3::ZERO, ChunkData::new());
+        press(&mut world, KeyCode::Y);
+        assert_eq!(voxels(&world), [Some(BlockType::Stone); 2]);
+        assert_eq!(world.resource::<EditHistory>().undo.len(), 1);
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::{ChunkData, WorldGen};
+
+// Called with the chunk position and its freshly generated voxels
+pub type GenerationHook = Arc<dyn Fn(IVec3, &mut ChunkData, &WorldGen) + Send + Sync>;
+
+// Extra generation passes run on every chunk after the base terrain and
+// ores, e.g. floating islands or custom ores. Hooks run in the order they
+// were added, each seeing the previous one's output.
+//
+// Chunks generate in no particular order and regenerate whenever they reload
+// unedited, on background threads, so a hook must be a pure function of its
+// arguments: draw any randomness from `position_rng`, never from shared state.
+// A hook may only write inside its own chunk, so features crossing a border
+// need to be placed from world coordinates by every chunk they touch.
+#[derive(Resource, Default, Clone)]
+pub struct GenerationHooks {
+    hooks: Vec<GenerationHook>,
+}
+
+impl GenerationHooks {
+    pub fn add(&mut self, hook: impl Fn(IVec3, &mut ChunkData, &WorldGen) + Send + Sync + 'static) {
+        self.hooks.push(Arc::new(hook));
+    }
+
+    pub fn run(&self, position: IVec3, chunk: &mut ChunkData, world_gen: &WorldGen) {
+        for hook in &self.hooks {
+            hook(position, chunk, world_gen);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::interaction::TargetedBlock;
+use crate::GameState;
+
+const CROSSHAIR_SIZE: f32 = 16.0;
+const CROSSHAIR_THICKNESS: f32 = 2.0;
+// Solid while a block is in reach, faint when a click would do nothing
+const TARGET_COLOR: Color = Color::WHITE;
+const NO_TARGET_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_crosshair).add_systems(Update, update_crosshair);
+    }
+}
+
+#[derive(Component)]
+struct Crosshair;
+
+// One bar of the crosshair's plus
+#[derive(Component)]
+struct CrosshairBar;
+
+fn spawn_crosshair(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Crosshair,
+        ))
+        .with_children(|root| {
+            for (width, height) in [(CROSSHAIR_SIZE, CROSSHAIR_THICKNESS), (CROSSHAIR_THICKNESS, CROSSHAIR_SIZE)] {
+                root.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(width),
+                            height: Val::Px(height),
+                            ..default()
+                        },
+                        background_color: NO_TARGET_COLOR.into(),
+                        ..default()
+                    },
+                    CrosshairBar,
+                ));
+            }
+        });
+}
+
+// Shown only in play, and lit up whenever a block is in reach
+fn update_crosshair(
+    state: Res<State<GameState>>,
+    target: Res<TargetedBlock>,
+    mut crosshair_query: Query<&mut Visibility, With<Crosshair>>,
+    mut bar_query: Query<&mut BackgroundColor, With<CrosshairBar>>,
+) {
+    let visibility = if *state.get() == GameState::Playing { Visibility::Inherited } else { Visibility::Hidden };
+    for mut crosshair in crosshair_query.iter_mut() {
+        if *crosshair != visibility {
+            *crosshair = visibility;
+        }
+    }
+
+    let color = if target.0.is_some() { TARGET_COLOR } else { NO_TARGET_COLOR };
+    for mut bar in bar_query.iter_mut() {
+        if bar.0 != color {
+            bar.0 = color;
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::fill::{
+    apply_fill, apply_pending_edits, copy_box, mark_fill_corner, paste_clipboard, rotate_clipboard, toggle_fill_tool,
+    FillTool, PendingEdits, VoxelClipboard,
+};
+use crate::history::{undo_redo, EditHistory};
+use crate::player::overlaps_player;
+use crate::settings::Settings;
+use crate::world::{raycast_render, RayHit};
+use crate::{
+    edit_block, queue_block_changes, ActionState, BlockChanged, BlockType, CursorGrabbed, Facing, GameState,
+    MovementMode, Player, PlayerAction, PlayerPhysics, WorldGen, WorldMap, WorldOrigin,
+};
+
+#[cfg(not(feature = "headless"))]
+const HIGHLIGHT_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+// Cracks are drawn unlit, so they still show on blocks deep in a cave
+#[cfg(not(feature = "headless"))]
+const CRACK_COLOR: Color = Color::rgb(1.0, 0.9, 0.7);
+// Cracks spreading from the middle of each face, and the kinks along each
+#[cfg(not(feature = "headless"))]
+const CRACKS_PER_FACE: usize = 4;
+#[cfg(not(feature = "headless"))]
+const CRACK_SEGMENTS: usize = 3;
+const HOTBAR_KEYS: [KeyCode; 4] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hotbar>()
+            .init_resource::<EditHistory>()
+            .init_resource::<FillTool>()
+            .init_resource::<VoxelClipboard>()
+            .init_resource::<PendingEdits>()
+            .init_resource::<TargetedBlock>()
+            .init_resource::<MiningState>()
+            .add_systems(
+                Update,
+                (
+                    select_hotbar_slot,
+                    toggle_fill_tool,
+                    // Clicks mark corners instead while the fill tool is out
+                    (break_block, place_block).run_if(|tool: Res<FillTool>| !tool.active),
+                    (mark_fill_corner, apply_fill, copy_box, rotate_clipboard, paste_clipboard)
+                        .run_if(|tool: Res<FillTool>| tool.active),
+                    undo_redo,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_equals(CursorGrabbed(true)))
+                    .before(queue_block_changes),
+            )
+            .add_systems(Update, apply_pending_edits.before(queue_block_changes))
+            // After this frame's edits, so it shows what the next click would hit
+            .add_systems(Update, update_target.after(undo_redo).after(apply_pending_edits));
+
+        #[cfg(not(feature = "headless"))]
+        app.add_systems(
+            Update,
+            (highlight_target.after(update_target), draw_cracks.after(update_target), crate::fill::draw_fill_selection),
+        );
+    }
+}
+
+// Blocks the player can place, picked with the number keys
+#[derive(Resource)]
+pub struct Hotbar {
+    pub slots: [BlockType; 4],
+    pub selected: usize,
+}
+
+impl Default for Hotbar {
+    fn default() -> Self {
+        Self {
+            slots: [BlockType::Dirt, BlockType::Stone, BlockType::Glowstone, BlockType::Log(Facing::Up)],
+            selected: 0,
+        }
+    }
+}
+
+impl Hotbar {
+    pub fn selected_block(&self) -> BlockType {
+        self.slots[self.selected]
+    }
+}
+
+// The player's line of sight and how far along it they can reach, shared by
+// every tool so they all agree on which block is in range
+#[derive(SystemParam)]
+pub struct PlayerSight<'w, 's> {
+    settings: Res<'w, Settings>,
+    world_origin: Res<'w, WorldOrigin>,
+    player_query: Query<'w, 's, (&'static Transform, &'static MovementMode), With<Player>>,
+    physics_query: Query<'w, 's, &'static PlayerPhysics, With<Player>>,
+}
+
+impl PlayerSight<'_, '_> {
+    // Render-space position the player looks from
+    pub fn eye(&self) -> Option<Vec3> {
+        let (transform, _) = self.player_query.get_single().ok()?;
+        Some(transform.translation)
+    }
+
+    // Which way the player stands, straight up for a player without physics
+    pub fn up(&self) -> Vec3 {
+        self.physics_query.get_single().map_or(Vec3::Y, |physics| physics.up)
+    }
+
+    // The solid block the player is looking at, if it is within reach
+    pub fn target(&self, world_map: &WorldMap) -> Option<RayHit> {
+        let (transform, &mode) = self.player_query.get_single().ok()?;
+        let reach = self.settings.reach(mode);
+        raycast_render(world_map, &self.world_origin, transform.translation, transform.forward(), reach)
+    }
+}
+
+// The block being mined and how far along it is, from 0 to 1
+#[derive(Resource, Default)]
+pub struct MiningState {
+    pub target: Option<IVec3>,
+    pub progress: f32,
+}
+
+// Holding the button mines the targeted block for its `hardness` in seconds,
+// then breaks it. Letting go or looking at another block starts over.
+fn break_block(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+    mut history: ResMut<EditHistory>,
+    mut mining: ResMut<MiningState>,
+    sight: PlayerSight,
+) {
+    let hit = sight.target(&world_map).filter(|hit| hit.block.is_breakable());
+    let (true, Some(hit)) = (actions.pressed(PlayerAction::Break), hit) else {
+        *mining = MiningState::default();
+        return;
+    };
+    if mining.target != Some(hit.voxel) {
+        *mining = MiningState { target: Some(hit.voxel), progress: 0.0 };
+    }
+    mining.progress = (mining.progress + time.delta_seconds() / hit.block.hardness()).min(1.0);
+    if mining.progress < 1.0 {
+        return;
+    }
+
+    *mining = MiningState::default();
+    if let Some(change) = edit_block(&mut world_map, &mut block_events, hit.voxel, BlockType::Air) {
+        history.record(change);
+    }
+}
+
+fn select_hotbar_slot(input: Res<Input<KeyCode>>, mut hotbar: ResMut<Hotbar>) {
+    if let Some(slot) = HOTBAR_KEYS.iter().position(|&key| input.just_pressed(key)) {
+        hotbar.selected = slot;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn place_block(
+    actions: Res<ActionState>,
+    mut world_map: ResMut<WorldMap>,
+    mut block_events: EventWriter<BlockChanged>,
+    mut history: ResMut<EditHistory>,
+    world_gen: Res<WorldGen>,
+    hotbar: Res<Hotbar>,
+    world_origin: Res<WorldOrigin>,
+    sight: PlayerSight,
+) {
+    if !actions.just_pressed(PlayerAction::Place) {
+        return;
+    }
+    let (Some(eye), Some(hit)) = (sight.eye(), sight.target(&world_map)) else {
+        return;
+    };
+
+    let target = hit.voxel + hit.normal;
+    let blocked = overlaps_player(eye, sight.up(), &world_origin, target);
+    if hit.normal == IVec3::ZERO || !world_gen.in_bounds(target.y) || blocked {
+        return;
+    }
+    if world_map.voxel(target).is_some_and(|block| !block.is_solid()) {
+        let block = hotbar.selected_block().placed_against(hit.normal);
+        if let Some(change) = edit_block(&mut world_map, &mut block_events, target, block) {
+            history.record(change);
+        }
+    }
+}
+
+// The block in reach under the crosshair, refreshed every frame for the HUD
+#[derive(Resource, Default)]
+pub struct TargetedBlock(pub Option<RayHit>);
+
+fn update_target(world_map: Res<WorldMap>, sight: PlayerSight, mut target: ResMut<TargetedBlock>) {
+    target.0 = sight.target(&world_map);
+}
+
+#[cfg(not(feature = "headless"))]
+fn highlight_target(world_origin: Res<WorldOrigin>, target: Res<TargetedBlock>, mut gizmos: Gizmos) {
+    if let Some(hit) = target.0 {
+        let scale = world_origin.voxel_scale;
+        let center = world_origin.to_render(hit.voxel) + Vec3::splat(0.5 * scale);
+        gizmos.cuboid(Transform::from_translation(center).with_scale(Vec3::splat(1.01 * scale)), HIGHLIGHT_COLOR);
+    }
+}
+
+// Jagged lines running out from the middle of every face of the block being
+// mined, reaching the edges as it is about to break. Each block gets its own
+// pattern, fixed so the cracks only grow rather than flicker.
+#[cfg(not(feature = "headless"))]
+fn draw_cracks(
+    world_origin: Res<WorldOrigin>,
+    target: Res<TargetedBlock>,
+    mining: Res<MiningState>,
+    mut gizmos: Gizmos,
+) {
+    use rand::Rng;
+
+    let (Some(voxel), Some(hit)) = (mining.target, target.0) else {
+        return;
+    };
+    if hit.voxel != voxel || mining.progress <= 0.0 {
+        return;
+    }
+    let scale = world_origin.voxel_scale;
+    let center = world_origin.to_render(voxel) + Vec3::splat(0.5 * scale);
+    let mut rng = crate::position_rng(0, voxel);
+    for normal in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z] {
+        // Just off the face so the lines aren't hidden inside it
+        let face = center + normal * 0.505 * scale;
+        let (u, v) = normal.any_orthonormal_pair();
+        for _ in 0..CRACKS_PER_FACE {
+            let angle = rng.gen_range(0.0, std::f32::consts::TAU);
+            let mut point = face;
+            let mut points = vec![point];
+            for segment in 0..CRACK_SEGMENTS {
+                let bend = angle + rng.gen_range(-0.6, 0.6);
+                let length = 0.5 * scale / CRACK_SEGMENTS as f32;
+                // Segments past the current progress are drawn later, once it gets there
+                let reached = (mining.progress * CRACK_SEGMENTS as f32 - segment as f32).clamp(0.0, 1.0);
+                if reached > 0.0 {
+                    point += (u * bend.cos() + v * bend.sin()) * length * reached;
+                    points.push(point);
+                }
+            }
+            gizmos.linestrip(points, CRACK_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::ChunkData;
+
+    // A world holding everything the block tools read and write, around
+    // `world_map` at `scale` render units per voxel, with no action held
+    fn interaction_world(world_map: WorldMap, scale: f32) -> World {
+        let mut world = World::new();
+        world.insert_resource(Settings::default());
+        world.insert_resource(WorldOrigin { chunk: IVec3::ZERO, voxel_scale: scale });
+        world.insert_resource(WorldGen::new(0));
+        world.insert_resource(world_map);
+        world.init_resource::<ActionState>();
+        world.init_resource::<Hotbar>();
+        world.insert_resource(Time::<()>::default());
+        world.init_resource::<Events<BlockChanged>>();
+        world.init_resource::<EditHistory>();
+        world.init_resource::<MiningState>();
+        world
+    }
+
+    // Looks along +x from the middle of voxel 0 at a single stone block `distance` voxels away
+    fn target_at(distance: i32, mode: MovementMode) -> Option<IVec3> {
+        let mut chunk = ChunkData::new();
+        chunk.set(IVec3::new(distance, 0, 0), BlockType::Stone);
+        let mut world_map = WorldMap::default();
+        world_map.chunks.insert(IVec3::ZERO, chunk);
+
+        let mut world = interaction_world(world_map, 1.0);
+        world.insert_resource(Settings { reach: 6.0, fly_reach: 12.0, ..default() });
+        world.spawn((Transform::from_xyz(0.5, 0.5, 0.5).looking_to(Vec3::X, Vec3::Y), mode, Player));
+        world.run_system_once(|sight: PlayerSight, world_map: Res<WorldMap>| {
+            sight.target(&world_map).map(|hit| hit.voxel)
+        })
+    }
+
+    #[test]
+    fn blocks_past_reach_are_out_of_range() {
+        // The ray enters voxel 6 after 5.5 blocks and voxel 7 after 6.5
+        assert_eq!(target_at(6, MovementMode::Walk), Some(IVec3::new(6, 0, 0)));
+        assert_eq!(target_at(7, MovementMode::Walk), None);
+    }
+
+    #[test]
+    fn holding_the_button_breaks_a_block_after_its_hardness() {
+        let stone = IVec3::new(3, 0, 0);
+        let mut chunk = ChunkData::new();
+        chunk.set(stone, BlockType::Stone);
+        let mut world_map = WorldMap::default();
+        world_map.chunks.insert(IVec3::ZERO, chunk);
+        let mut actions = ActionState::default();
+        actions.set(PlayerAction::Break, true);
+
+        let mut world = interaction_world(world_map, 1.0);
+        world.insert_resource(actions);
+        let player = (Transform::from_xyz(0.5, 0.5, 0.5).looking_to(Vec3::X, Vec3::Y), MovementMode::Walk, Player);
+        world.spawn(player);
+
+        // Stone takes 1.2 seconds
+        let mut mine_for = |seconds: f32| {
+            world.resource_mut::<Time<()>>().advance_by(std::time::Duration::from_secs_f32(seconds));
+            world.run_system_once(break_block);
+            world.resource::<WorldMap>().voxel(stone)
+        };
+        assert_eq!(mine_for(0.5), Some(BlockType::Stone));
+        assert_eq!(mine_for(0.5), Some(BlockType::Stone));
+        assert_eq!(mine_for(0.5), Some(BlockType::Air));
+    }
+
+    #[test]
+    fn releasing_the_button_resets_mining() {
+        let mut world = interaction_world(WorldMap::default(), 1.0);
+        world.insert_resource(MiningState { target: Some(IVec3::ONE), progress: 0.8 });
+        world.spawn((Transform::default(), MovementMode::Walk, Player));
+
+        world.run_system_once(break_block);
+        let mining = world.resource::<MiningState>();
+        assert_eq!((mining.target, mining.progress), (None, 0.0));
+    }
+
+    #[test]
+    fn flying_reaches_further() {
+        assert_eq!(target_at(7, MovementMode::Fly), Some(IVec3::new(7, 0, 0)));
+        assert_eq!(target_at(12, MovementMode::FlyCollide), Some(IVec3::new(12, 0, 0)));
+        assert_eq!(target_at(13, MovementMode::Fly), None);
+    }
+
+    // A player in voxel column (0, 0) looks along +x at stone two blocks away
+    // and builds against it. The player is sized in blocks, so at every scale
+    // their shoulder keeps them from building into the voxel it pokes into,
+    // until they step back to the middle of their own.
+    #[test]
+    fn placing_and_breaking_follow_the_voxel_scale() {
+        use crate::player::EYE_HEIGHT;
+
+        for scale in [0.5, 2.0] {
+            let (stone, beside) = (IVec3::new(2, 2, 0), IVec3::new(1, 2, 0));
+            let mut chunk = ChunkData::new();
+            chunk.set(stone, BlockType::Stone);
+            let mut world_map = WorldMap::default();
+            world_map.chunks.insert(IVec3::ZERO, chunk);
+
+            let mut world = interaction_world(world_map, scale);
+            let player = world.spawn((Transform::default(), MovementMode::Walk, Player)).id();
+            let act = |world: &mut World, action: PlayerAction, x: f32| {
+                let eye = Vec3::new(x, 1.0 + EYE_HEIGHT, 0.5) * scale;
+                let transform = Transform::from_translation(eye).looking_to(Vec3::X, Vec3::Y);
+                *world.get_mut::<Transform>(player).unwrap() = transform;
+                let mut actions = ActionState::default();
+                actions.set(action, true);
+                world.insert_resource(actions);
+                world.resource_mut::<Time<()>>().advance_by(std::time::Duration::from_secs(5));
+                match action {
+                    PlayerAction::Place => world.run_system_once(place_block),
+                    _ => world.run_system_once(break_block),
+                }
+                world.resource::<WorldMap>().voxel(beside)
+            };
+
+            // Half a block wide, standing at 0.75 reaches into x = 1
+            assert_eq!(act(&mut world, PlayerAction::Place, 0.75), Some(BlockType::Air), "at scale {scale}");
+            assert_eq!(act(&mut world, PlayerAction::Place, 0.5), Some(BlockType::Dirt), "at scale {scale}");
+            assert_eq!(act(&mut world, PlayerAction::Break, 0.5), Some(BlockType::Air), "at scale {scale}");
+        }
+    }
+}
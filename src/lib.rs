@@ -0,0 +1,3154 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use bevy::utils::{HashMap, HashSet};
+#[cfg(not(feature = "headless"))]
+use bevy::window::WindowFocused;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+use noise::{NoiseFn, Perlin};
+use rand::Rng;
+
+mod actions;
+#[cfg(not(feature = "headless"))]
+mod atlas;
+mod camera;
+mod config;
+#[cfg(not(feature = "headless"))]
+mod debug;
+mod fill;
+mod fluid;
+mod head_bob;
+mod history;
+mod hooks;
+#[cfg(not(feature = "headless"))]
+mod hud;
+mod interaction;
+mod lighting;
+mod loading;
+mod lod;
+#[cfg(not(feature = "headless"))]
+mod menu;
+mod orientation;
+mod origin;
+mod pacing;
+#[cfg(not(feature = "headless"))]
+mod particles;
+mod player;
+mod region;
+mod rng;
+mod save;
+mod settings;
+mod shape;
+mod sky;
+mod smooth;
+mod snapshot;
+#[cfg(not(feature = "headless"))]
+mod sound;
+mod surface;
+mod terrain_noise;
+mod timings;
+mod world;
+
+use actions::ActionsPlugin;
+#[cfg(not(feature = "headless"))]
+pub use atlas::{AtlasPlugin, BlockAtlas};
+pub use actions::{ActionBindings, ActionState, Binding, PlayerAction};
+use camera::CameraPullBack;
+pub use camera::CameraMode;
+use config::ConfigPlugin;
+pub use config::{ConfigFile, UserConfig, MAX_RENDER_DISTANCE};
+#[cfg(not(feature = "headless"))]
+pub use debug::DebugHudPlugin;
+pub use fluid::{FluidPlugin, FluidSim};
+pub use fill::{FillTool, VoxelClipboard};
+pub use history::EditHistory;
+pub use head_bob::HeadBob;
+pub use hooks::{GenerationHook, GenerationHooks};
+pub use interaction::{Hotbar, MiningState, TargetedBlock};
+use interaction::InteractionPlugin;
+use loading::LoadingPlugin;
+use pacing::FramePacingPlugin;
+use sky::SkyPlugin;
+pub use surface::SurfaceCache;
+use surface::clear_surface_cache_on_change;
+pub use terrain_noise::NoiseKind;
+use terrain_noise::Noise2d;
+pub use loading::LoadingProgress;
+use lighting::{light_brightness, light_new_chunk, relight_block_changes, LightChannel};
+use lod::{animate_lod_morphs, coarse_block, update_chunk_lod, ChunkLod, LodMorph};
+use origin::{apply_voxel_scale, rebase_origin};
+pub use world::{raycast_voxels, RayHit};
+use smooth::add_smooth_surface;
+pub use snapshot::{BorderVoxel, NeighborSnapshot};
+pub use origin::WorldOrigin;
+pub use orientation::{AtlasTile, FaceTexture, Facing, ATTRIBUTE_ATLAS_TILE};
+#[cfg(not(feature = "headless"))]
+use hud::HudPlugin;
+#[cfg(not(feature = "headless"))]
+pub use menu::MainMenuPlugin;
+#[cfg(not(feature = "headless"))]
+use menu::PauseMenuPlugin;
+#[cfg(not(feature = "headless"))]
+pub use particles::{BreakParticles, ParticlesPlugin};
+pub use player::{MovementMode, PhysicsConfig, Player, PlayerPhysics, PlayerPlugin, Traction, EYE_HEIGHT};
+pub use region::{SavePlugin, WorldSave, REGION_SIZE};
+pub use rng::position_rng;
+pub use save::{
+    decode_chunk, decode_player, encode_chunk, encode_player, PlayerState, SaveError, FORMAT_VERSION,
+    PLAYER_FORMAT_VERSION,
+};
+pub use settings::{BiomeTints, FaceShading, MeshStyle, MeshTopology, Settings, TextureFilter, VoidRescue};
+pub use shape::{BlockAabb, EMPTY_SHAPE, FULL_SHAPE, LOWER_SLAB_SHAPE};
+pub use sky::{default_sky_ramp, sample_sky, SkyKeyframe, TimeOfDay};
+#[cfg(not(feature = "headless"))]
+pub use sound::{SoundAssets, SoundGroup, SoundPlugin};
+use timings::{record, timed, ChunkTimings};
+
+const CHUNK_SIZE: i32 = 16;
+const BIOME_SCALE: f64 = 0.004;
+// Terrain noise frequency and the surface's base and amplitude, in render
+// units rather than blocks so hills keep their size at any voxel scale
+const TERRAIN_FREQUENCY: f64 = 0.02;
+const TERRAIN_BASE: f64 = 10.0;
+const TERRAIN_AMPLITUDE: f64 = 10.0;
+const BIOME_BLEND_RADIUS: i32 = 4;
+const SEA_LEVEL: i32 = 8;
+const DIRT_DEPTH: i32 = 4;
+// Layers of sand on a beach, and how far below sea level the shore's sand reaches
+const SAND_DEPTH: i32 = 3;
+const SHORE_DEPTH: i32 = 3;
+const DIRT_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+const WATER_COLOR: Color = Color::rgba(0.2, 0.4, 0.8, 0.7);
+const FLOWING_WATER_HEIGHT: f32 = 0.85;
+// Extra chunks beyond the render distance kept loaded before unloading
+const UNLOAD_MARGIN: i32 = 1;
+// Chunk columns on each side of the spawn generated before the first frame
+const SPAWN_PRELOAD_RADIUS: i32 = 1;
+// Cosine of the half angle of the cone ahead of the player generated first
+const VIEW_CONE_COS: f32 = 0.5;
+// How many times further away a column straight behind the player counts
+const BEHIND_PRIORITY: f32 = 3.0;
+
+#[derive(Component)]
+struct Chunk {
+    position: IVec3,
+}
+
+// Size of a chunk's meshes summed over every material group, kept up to date
+// each time the chunk is meshed, for diagnostics and tests
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ChunkMeshStats {
+    pub vertices: usize,
+    pub indices: usize,
+}
+
+#[cfg(not(feature = "headless"))]
+#[derive(Component)]
+struct UnderwaterOverlay;
+
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameState {
+    // Choosing a seed before anything generates; only with `MainMenuPlugin`
+    MainMenu,
+    // Chunks stream in around the player, who can't move yet
+    #[default]
+    Loading,
+    Playing,
+    Paused,
+}
+
+// Whether the cursor is captured for mouse look; mouse input is ignored while
+// it isn't, e.g. after the window loses focus until the player clicks back in
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CursorGrabbed(pub bool);
+
+#[derive(Resource, Default)]
+struct ChunkIndex {
+    chunks: HashMap<IVec3, ChunkEntry>,
+}
+
+// Every conversion between absolute voxel and chunk coordinates goes through
+// these three. Division rounds towards negative infinity, so voxel -1 is the
+// last of chunk -1 rather than part of chunk 0.
+
+// The chunk holding an absolute voxel
+pub fn world_to_chunk(world_pos: IVec3) -> IVec3 {
+    world_pos.div_euclid(IVec3::splat(CHUNK_SIZE))
+}
+
+// The absolute voxel at a chunk's low corner
+pub fn chunk_to_world_origin(chunk_pos: IVec3) -> IVec3 {
+    chunk_pos * CHUNK_SIZE
+}
+
+// The chunk holding an absolute voxel, and the voxel's position inside it
+pub fn world_to_local(world_pos: IVec3) -> (IVec3, IVec3) {
+    (world_to_chunk(world_pos), world_pos.rem_euclid(IVec3::splat(CHUNK_SIZE)))
+}
+
+// Voxel data of every loaded chunk, keyed by chunk position
+#[derive(Resource, Default)]
+pub struct WorldMap {
+    chunks: HashMap<IVec3, ChunkData>,
+}
+
+impl WorldMap {
+    // Positions of every generated chunk, in no particular order
+    pub fn generated_chunks(&self) -> impl Iterator<Item = IVec3> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    pub fn contains(&self, chunk_pos: IVec3) -> bool {
+        self.chunks.contains_key(&chunk_pos)
+    }
+
+    // Number of generated chunks
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    // `None` when the chunk holding the voxel isn't loaded
+    pub fn voxel(&self, world_pos: IVec3) -> Option<BlockType> {
+        let (chunk_pos, local) = world_to_local(world_pos);
+        self.chunks.get(&chunk_pos).map(|chunk| chunk.get(local))
+    }
+
+    // Returns the replaced block, or `None` if the chunk isn't loaded
+    fn set_voxel(&mut self, world_pos: IVec3, block: BlockType) -> Option<BlockType> {
+        let (chunk_pos, local) = world_to_local(world_pos);
+        let chunk = self.chunks.get_mut(&chunk_pos)?;
+        let old = chunk.get(local);
+        chunk.set(local, block);
+        Some(old)
+    }
+
+    // Light level of a voxel, `None` when its chunk isn't loaded
+    fn light(&self, world_pos: IVec3, channel: LightChannel) -> Option<u8> {
+        let (chunk_pos, local) = world_to_local(world_pos);
+        self.chunks.get(&chunk_pos).map(|chunk| chunk.light(local, channel))
+    }
+
+    fn set_light(&mut self, world_pos: IVec3, channel: LightChannel, level: u8) {
+        let (chunk_pos, local) = world_to_local(world_pos);
+        if let Some(chunk) = self.chunks.get_mut(&chunk_pos) {
+            chunk.set_light(local, channel, level);
+        }
+    }
+
+    // Terrain density at a voxel center, `None` when its chunk isn't loaded
+    pub fn density(&self, world_pos: IVec3) -> Option<f32> {
+        let (chunk_pos, local) = world_to_local(world_pos);
+        self.chunks.get(&chunk_pos).map(|chunk| chunk.density(local))
+    }
+}
+
+// Sent for every block edit after world generation; remeshing is driven off
+// these, and gameplay (sounds, particles) can react to them too
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BlockChanged {
+    pub world_pos: IVec3,
+    pub old: BlockType,
+    pub new: BlockType,
+}
+
+// Writes a block into a loaded chunk and announces the change
+// Returns the change made, or `None` if the voxel isn't loaded
+fn edit_block(
+    world_map: &mut WorldMap,
+    events: &mut EventWriter<BlockChanged>,
+    world_pos: IVec3,
+    new: BlockType,
+) -> Option<BlockChanged> {
+    let old = world_map.set_voxel(world_pos, new)?;
+    let change = BlockChanged { world_pos, old, new };
+    events.send(change);
+    Some(change)
+}
+
+// Chunks whose voxels changed and need their meshes rebuilt
+#[derive(Resource, Default)]
+struct RemeshQueue {
+    chunks: HashSet<IVec3>,
+}
+
+impl RemeshQueue {
+    // Queues the chunk holding the voxel, plus any neighbor it borders
+    fn queue_voxel(&mut self, world_pos: IVec3) {
+        let (chunk_pos, local) = world_to_local(world_pos);
+        self.chunks.insert(chunk_pos);
+
+        for axis in [IVec3::X, IVec3::Y, IVec3::Z] {
+            let offset = local.dot(axis);
+            if offset == 0 {
+                self.chunks.insert(chunk_pos - axis);
+            } else if offset == CHUNK_SIZE - 1 {
+                self.chunks.insert(chunk_pos + axis);
+            }
+        }
+    }
+}
+
+// Blocks sharing surface properties, meshed and drawn together. Most blocks
+// are `Opaque`, which lives on the chunk entity itself so batching stays good;
+// every other group gets a child entity with its own material.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MaterialGroup {
+    Opaque,
+    Glowing,
+    // Cut out rather than blended, so foliage needs no sorting
+    Leaves,
+    Water,
+    Glass,
+}
+
+impl MaterialGroup {
+    const ALL: [MaterialGroup; 5] = [
+        MaterialGroup::Opaque,
+        MaterialGroup::Glowing,
+        MaterialGroup::Leaves,
+        MaterialGroup::Water,
+        MaterialGroup::Glass,
+    ];
+    const COUNT: usize = MaterialGroup::ALL.len();
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// Materials shared by every chunk, so chunks can be batched together.
+// Changing `surface` updates the terrain materials in place.
+#[derive(Resource)]
+pub struct ChunkMaterials {
+    materials: [Handle<StandardMaterial>; MaterialGroup::COUNT],
+    pub surface: Surface,
+}
+
+impl ChunkMaterials {
+    fn get(&self, group: MaterialGroup) -> Handle<StandardMaterial> {
+        self.materials[group.index()].clone()
+    }
+}
+
+// How terrain responds to light; the defaults read as matte rather than plastic
+#[derive(Clone, Copy, Debug)]
+pub struct Surface {
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+    pub reflectance: f32,
+}
+
+impl Default for Surface {
+    fn default() -> Self {
+        Self {
+            perceptual_roughness: 0.95,
+            metallic: 0.0,
+            reflectance: 0.1,
+        }
+    }
+}
+
+impl FromWorld for ChunkMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        Self {
+            materials: MaterialGroup::ALL.map(|group| {
+                materials.add(match group {
+                    MaterialGroup::Opaque => Color::WHITE.into(),
+                    MaterialGroup::Glowing => StandardMaterial {
+                        base_color: Color::WHITE,
+                        emissive: Color::rgb(0.6, 0.5, 0.3),
+                        ..default()
+                    },
+                    // Texels below half alpha are discarded, leaving gaps in the canopy
+                    MaterialGroup::Leaves => StandardMaterial {
+                        base_color: Color::WHITE,
+                        alpha_mode: AlphaMode::Mask(0.5),
+                        double_sided: true,
+                        cull_mode: None,
+                        ..default()
+                    },
+                    MaterialGroup::Water | MaterialGroup::Glass => StandardMaterial {
+                        base_color: Color::WHITE,
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    },
+                })
+            }),
+            surface: Surface::default(),
+        }
+    }
+}
+
+fn apply_chunk_surface(chunk_materials: Res<ChunkMaterials>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    if !chunk_materials.is_changed() {
+        return;
+    }
+    let surface = chunk_materials.surface;
+    for group in [MaterialGroup::Opaque, MaterialGroup::Glowing, MaterialGroup::Leaves] {
+        if let Some(material) = materials.get_mut(&chunk_materials.materials[group.index()]) {
+            material.perceptual_roughness = surface.perceptual_roughness;
+            material.metallic = surface.metallic;
+            material.reflectance = surface.reflectance;
+        }
+    }
+}
+
+struct ChunkEntry {
+    entity: Entity,
+    // Child entities carrying the non-opaque groups the chunk contains
+    submeshes: HashMap<MaterialGroup, Entity>,
+    empty: bool,
+    modified: bool,
+    lod: ChunkLod,
+    // When the chunk last left the unload distance, if it is still outside it
+    out_of_range_since: Option<Instant>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BlockType {
+    Air,
+    Grass,
+    Dirt,
+    Stone,
+    CoalOre,
+    IronOre,
+    Water,
+    // Water spread from a source, with its remaining flow distance
+    FlowingWater(u8),
+    Bedrock,
+    Glowstone,
+    Leaves,
+    Sand,
+    // A trunk with its rings facing along the axis of `Facing`
+    Log(Facing),
+    // The lower half of a stone block; it still draws as a full cube
+    StoneSlab,
+    Glass,
+}
+
+impl BlockType {
+    fn is_solid(self) -> bool {
+        self != BlockType::Air && !self.is_water()
+    }
+
+    fn is_water(self) -> bool {
+        matches!(self, BlockType::Water | BlockType::FlowingWater(_))
+    }
+
+    // Blocks that can be seen through, so the faces behind them still show
+    fn is_transparent(self) -> bool {
+        matches!(self, BlockType::Air | BlockType::Leaves | BlockType::Glass) || self.is_water()
+    }
+
+    // Blocks drawn as a whole cube; the rest draw their collision shape and
+    // leave part of the voxel open, so they hide no neighbor's face
+    fn fills_voxel(self) -> bool {
+        self != BlockType::StoneSlab
+    }
+
+    // Whether this block's face against `neighbor` is drawn: never behind an
+    // opaque block, nor between two of the same see-through kind, which would
+    // only draw planes inside one body of water or glass. Leaves keep theirs
+    // so a canopy looks full through its gaps.
+    fn shows_face_against(self, neighbor: BlockType) -> bool {
+        if !neighbor.fills_voxel() {
+            return true;
+        }
+        let same_kind = self == neighbor || self.is_water() && neighbor.is_water();
+        neighbor.is_transparent() && !(same_kind && self != BlockType::Leaves)
+    }
+
+    // Boxes the player collides with, in block-local space
+    pub fn collision_shape(self) -> &'static [BlockAabb] {
+        match self {
+            BlockType::StoneSlab => LOWER_SLAB_SHAPE,
+            block if block.is_solid() => FULL_SHAPE,
+            _ => EMPTY_SHAPE,
+        }
+    }
+
+    fn is_breakable(self) -> bool {
+        self.hardness().is_finite()
+    }
+
+    // Seconds of mining it takes to break, infinite for blocks that can't be
+    pub fn hardness(self) -> f32 {
+        match self {
+            BlockType::Leaves => 0.2,
+            BlockType::Glass => 0.3,
+            BlockType::Dirt | BlockType::Grass | BlockType::Sand => 0.4,
+            BlockType::Glowstone => 0.5,
+            BlockType::Log(_) => 0.8,
+            BlockType::Stone | BlockType::StoneSlab => 1.2,
+            BlockType::CoalOre | BlockType::IronOre => 1.5,
+            BlockType::Air | BlockType::Water | BlockType::FlowingWater(_) | BlockType::Bedrock => f32::INFINITY,
+        }
+    }
+
+    fn material_group(self) -> MaterialGroup {
+        match self {
+            BlockType::Water | BlockType::FlowingWater(_) => MaterialGroup::Water,
+            BlockType::Glowstone => MaterialGroup::Glowing,
+            BlockType::Leaves => MaterialGroup::Leaves,
+            BlockType::Glass => MaterialGroup::Glass,
+            _ => MaterialGroup::Opaque,
+        }
+    }
+
+    // Block light given off, seeding the flood fill in `lighting`
+    fn emission(self) -> u8 {
+        match self {
+            BlockType::Glowstone => 14,
+            _ => 0,
+        }
+    }
+
+    // Natural terrain, colored by `BiomeTints`
+    fn is_biome_tinted(self) -> bool {
+        matches!(self, BlockType::Dirt | BlockType::Grass | BlockType::Stone | BlockType::Sand | BlockType::Leaves)
+    }
+
+    fn color(self) -> Color {
+        match self {
+            BlockType::Dirt | BlockType::Grass => DIRT_COLOR,
+            BlockType::Stone | BlockType::StoneSlab => Color::rgb(0.4, 0.4, 0.45),
+            BlockType::CoalOre => Color::rgb(0.15, 0.15, 0.15),
+            BlockType::IronOre => Color::rgb(0.7, 0.55, 0.45),
+            BlockType::Water | BlockType::FlowingWater(_) => WATER_COLOR,
+            BlockType::Bedrock => Color::rgb(0.15, 0.12, 0.12),
+            BlockType::Glowstone => Color::rgb(1.0, 0.85, 0.5),
+            BlockType::Leaves => Color::rgb(0.25, 0.5, 0.2),
+            BlockType::Sand => Color::rgb(0.86, 0.8, 0.58),
+            BlockType::Log(_) => Color::rgb(0.45, 0.32, 0.2),
+            BlockType::Glass => Color::rgba(0.85, 0.92, 0.95, 0.3),
+            BlockType::Air => Color::NONE,
+        }
+    }
+
+    // The atlas tile drawn on the face pointing `face`, turned to follow the
+    // block's orientation
+    pub fn face_texture(self, face: Facing) -> FaceTexture {
+        let tile = match self {
+            BlockType::Grass => match face {
+                Facing::Up => AtlasTile::GrassTop,
+                Facing::Down => AtlasTile::Dirt,
+                _ => AtlasTile::GrassSide,
+            },
+            BlockType::Log(axis) => {
+                if face.axis() == axis.axis() {
+                    return FaceTexture::new(AtlasTile::LogEnd);
+                }
+                // Bark grain follows the log, which lies along either the face's u or v
+                let rotation = if face.uv_axes().0 == axis.axis() { 1 } else { 0 };
+                return FaceTexture { tile: AtlasTile::LogBark, rotation };
+            }
+            BlockType::Dirt => AtlasTile::Dirt,
+            BlockType::Stone | BlockType::StoneSlab => AtlasTile::Stone,
+            BlockType::CoalOre => AtlasTile::CoalOre,
+            BlockType::IronOre => AtlasTile::IronOre,
+            // Air is never drawn
+            BlockType::Water | BlockType::FlowingWater(_) | BlockType::Air => AtlasTile::Water,
+            BlockType::Bedrock => AtlasTile::Bedrock,
+            BlockType::Glowstone => AtlasTile::Glowstone,
+            BlockType::Leaves => AtlasTile::Leaves,
+            BlockType::Sand => AtlasTile::Sand,
+            BlockType::Glass => AtlasTile::Glass,
+        };
+        FaceTexture::new(tile)
+    }
+
+    // The block as placed against a face pointing `normal`; directional
+    // blocks turn to face along it
+    pub fn placed_against(self, normal: IVec3) -> BlockType {
+        match self {
+            BlockType::Log(_) => BlockType::Log(Facing::nearest(normal.as_vec3())),
+            block => block,
+        }
+    }
+}
+
+// An ore replaces stone wherever its noise field exceeds `threshold` at or
+// below `max_y`. Lower thresholds make the ore more common.
+struct OreLayer {
+    block: BlockType,
+    max_y: i32,
+    scale: f64,
+    threshold: f64,
+    // Chance a voxel inside one of the noise's pockets is actually ore, so
+    // pockets come out speckled rather than as solid blobs
+    density: f64,
+}
+
+const ORE_LAYERS: [OreLayer; 2] = [
+    OreLayer {
+        block: BlockType::CoalOre,
+        max_y: 14,
+        scale: 0.15,
+        threshold: 0.55,
+        density: 0.7,
+    },
+    OreLayer {
+        block: BlockType::IronOre,
+        max_y: 8,
+        scale: 0.2,
+        threshold: 0.65,
+        density: 0.5,
+    },
+];
+
+#[derive(Resource, Clone)]
+pub struct WorldGen {
+    pub seed: u32,
+    noise: NoiseKind,
+    terrain: Noise2d,
+    temperature: Noise2d,
+    humidity: Noise2d,
+    // One noise field per entry in `ORE_LAYERS`; ore pockets stay Perlin
+    // whatever `noise` is, being 3D and rarely large enough to show its grid
+    ores: Vec<Perlin>,
+    // Inclusive world Y bounds; bedrock sits at `bottom_y` and nothing
+    // generates outside them
+    bottom_y: i32,
+    top_y: i32,
+    // Render units per voxel, mirroring `Settings::voxel_scale`. The surface
+    // is shaped in render units and then cut into blocks of this size, so a
+    // smaller scale draws the same hills with more blocks. Sea level, the
+    // world's height bounds, soil depths, biomes and ores stay in blocks.
+    voxel_scale: f64,
+    // Whether `clamp_surface` has warned yet. Shared by the copies handed to
+    // generation tasks, and fresh for every rebuilt generator.
+    clamp_warned: Arc<AtomicBool>,
+}
+
+impl WorldGen {
+    fn new(seed: u32) -> Self {
+        Self::with_noise(seed, NoiseKind::default())
+    }
+
+    pub fn with_noise(seed: u32, noise: NoiseKind) -> Self {
+        Self {
+            seed,
+            noise,
+            terrain: noise.build(seed),
+            temperature: noise.build(seed.wrapping_add(1)),
+            humidity: noise.build(seed.wrapping_add(2)),
+            ores: (0..ORE_LAYERS.len() as u32).map(|i| Perlin::new(seed.wrapping_add(3 + i))).collect(),
+            bottom_y: 0,
+            top_y: 2 * CHUNK_SIZE - 1,
+            voxel_scale: 1.0,
+            clamp_warned: Arc::default(),
+        }
+    }
+
+    // The same generator with another seed, e.g. one picked in the main menu
+    pub fn with_seed(&self, seed: u32) -> Self {
+        let (bottom_y, top_y, voxel_scale) = (self.bottom_y, self.top_y, self.voxel_scale);
+        WorldGen { bottom_y, top_y, voxel_scale, ..WorldGen::with_noise(seed, self.noise) }
+    }
+
+    // The same generator over other world Y bounds, both inclusive; a top
+    // below the bottom leaves a world one block high
+    pub fn with_bounds(&self, bottom_y: i32, top_y: i32) -> Self {
+        WorldGen { bottom_y, top_y: top_y.max(bottom_y), ..self.clone() }
+    }
+
+    // Vertical chunk positions that overlap the world bounds
+    pub fn chunk_y_range(&self) -> std::ops::RangeInclusive<i32> {
+        self.bottom_y.div_euclid(CHUNK_SIZE)..=self.top_y.div_euclid(CHUNK_SIZE)
+    }
+
+    pub fn in_bounds(&self, y: i32) -> bool {
+        (self.bottom_y..=self.top_y).contains(&y)
+    }
+
+    // Thresholds the density into a block: solid ground is layered by depth
+    // below the column's `height`, open space fills with water below sea level
+    fn block_for_density(&self, y: i32, density: f32, height: i32, beach: bool) -> BlockType {
+        if !self.in_bounds(y) {
+            BlockType::Air
+        } else if y == self.bottom_y {
+            BlockType::Bedrock
+        } else if density <= 0.0 {
+            if y < SEA_LEVEL {
+                BlockType::Water
+            } else {
+                BlockType::Air
+            }
+        } else if beach && y >= height - SAND_DEPTH {
+            BlockType::Sand
+        } else if y < height - DIRT_DEPTH {
+            BlockType::Stone
+        } else if y < height - 1 {
+            BlockType::Dirt
+        } else {
+            BlockType::Grass
+        }
+    }
+
+    // Whether a column with its surface at `height` is shoreline: a band from
+    // just under the water up to a biome-dependent height above it. Decided
+    // per world column, so the shore carries on across chunk borders.
+    pub fn is_beach(&self, world_x: i32, world_z: i32, height: i32) -> bool {
+        let surface = height - 1;
+        (SEA_LEVEL - SHORE_DEPTH..SEA_LEVEL + beach_width(self.biome_at(world_x, world_z))).contains(&surface)
+    }
+
+    // The biome of a world column, from the climate noise alone, so it can be
+    // asked of any column without generating its chunks
+    pub fn biome_at(&self, world_x: i32, world_z: i32) -> Biome {
+        let (t, h) = climate_at(&self.temperature, &self.humidity, world_x, world_z);
+
+        match (t > 0.25, t < -0.25, h > 0.0) {
+            (true, _, false) => Biome::Desert,
+            (true, _, true) => Biome::Savanna,
+            (_, true, false) => Biome::Tundra,
+            (_, true, true) => Biome::Taiga,
+            (_, _, false) => Biome::Plains,
+            (_, _, true) => Biome::Forest,
+        }
+    }
+
+    // Sampled in world space, so veins continue across chunk borders
+    fn ore_at(&self, position: IVec3) -> Option<BlockType> {
+        let mut rng = position_rng(self.seed, position);
+        ORE_LAYERS.iter().zip(&self.ores).find_map(|(layer, noise)| {
+            if position.y > layer.max_y {
+                return None;
+            }
+            let point = position.as_dvec3() * layer.scale;
+            let in_pocket = noise.get([point.x, point.y, point.z]) > layer.threshold;
+            (in_pocket && rng.gen_bool(layer.density)).then_some(layer.block)
+        })
+    }
+
+    // Height of the solid ground in whole blocks: the number of voxels from
+    // y = 0 whose centers lie under the surface, so it agrees with the density.
+    // Like `biome_at`, a pure function of the column, chunks or not.
+    pub fn surface_height_at(&self, world_x: i32, world_z: i32) -> i32 {
+        solid_height(self.surface_at(world_x, world_z))
+    }
+
+    // Terrain height in blocks before it is rounded to whole blocks
+    fn surface_at(&self, world_x: i32, world_z: i32) -> f64 {
+        let frequency = TERRAIN_FREQUENCY * self.voxel_scale;
+        let noise = self.terrain.get([world_x as f64 * frequency, world_z as f64 * frequency]);
+        self.clamp_surface((noise * TERRAIN_AMPLITUDE + TERRAIN_BASE) / self.voxel_scale)
+    }
+
+    // Keeps the surface inside the world however extreme the noise settings,
+    // so heights derived from it stay in range; NaN becomes the world floor
+    fn clamp_surface(&self, surface: f64) -> f64 {
+        let (min, max) = (self.bottom_y as f64, (self.top_y + 1) as f64);
+        if (min..=max).contains(&surface) {
+            return surface;
+        }
+        if !self.clamp_warned.swap(true, Ordering::Relaxed) {
+            warn!("Terrain surface {surface} is outside the world's height [{min}, {max}]; clamping it");
+        }
+        if surface.is_nan() {
+            min
+        } else {
+            surface.clamp(min, max)
+        }
+    }
+
+    // The one source of terrain shape, shared by both mesh styles: positive
+    // inside solid ground and negative in open space, clamped to a block
+    // either way. Blocks are derived from its sign, the smooth mesher reads
+    // it directly for the sub-block detail.
+    pub fn density_at(&self, position: IVec3) -> f32 {
+        self.column_density(position.y, self.surface_at(position.x, position.z))
+    }
+
+    fn column_density(&self, y: i32, surface: f64) -> f32 {
+        if y < self.bottom_y {
+            1.0
+        } else if y > self.top_y {
+            -1.0
+        } else {
+            (surface - (y as f64 + 0.5)).clamp(-1.0, 1.0) as f32
+        }
+    }
+
+    // Base terrain and ores only, without anything added by `GenerationHooks`
+    pub fn voxel_at(&self, position: IVec3) -> BlockType {
+        let surface = self.surface_at(position.x, position.z);
+        let height = solid_height(surface);
+        let beach = self.is_beach(position.x, position.z, height);
+        match self.block_for_density(position.y, self.column_density(position.y, surface), height, beach) {
+            BlockType::Stone => self.ore_at(position).unwrap_or(BlockType::Stone),
+            block => block,
+        }
+    }
+}
+
+fn solid_height(surface: f64) -> i32 {
+    (surface - 0.5).ceil() as i32
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Biome {
+    Desert,
+    Savanna,
+    Plains,
+    Forest,
+    Taiga,
+    Tundra,
+}
+
+// Stands in for `DefaultPlugins` without a window or GPU: just enough of Bevy
+// for generation and movement to run, e.g. in CI
+#[cfg(feature = "headless")]
+pub struct HeadlessPlugin;
+
+#[cfg(feature = "headless")]
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), bevy::input::InputPlugin))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>();
+    }
+}
+
+// Terrain generation, chunk streaming around the `Player` entity, remeshing
+// and block interaction. Add `PlayerPlugin` as well for the built-in controller.
+pub struct VoxelWorldPlugin {
+    pub seed: u32,
+    // Spawn the player camera and a light; turn off when the host app
+    // provides its own (its camera needs a `Player` to drive streaming)
+    pub spawn_camera: bool,
+}
+
+impl Default for VoxelWorldPlugin {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            spawn_camera: true,
+        }
+    }
+}
+
+impl Plugin for VoxelWorldPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(not(feature = "headless"))]
+        app.add_plugins((ParticlesPlugin, SoundPlugin, PauseMenuPlugin, HudPlugin, AtlasPlugin))
+            .add_systems(Startup, spawn_underwater_overlay)
+            .add_systems(Update, (underwater_effect, apply_camera_clipping, apply_msaa));
+
+        if self.spawn_camera {
+            app.add_systems(Startup, spawn_camera);
+        }
+
+        app.add_state::<GameState>()
+            .add_plugins((
+                ActionsPlugin,
+                InteractionPlugin,
+                FluidPlugin,
+                SavePlugin,
+                LoadingPlugin,
+                SkyPlugin,
+                ConfigPlugin,
+                FramePacingPlugin,
+            ))
+            .insert_resource(WorldGen::new(self.seed))
+            .init_resource::<Settings>()
+            .init_resource::<ChunkIndex>()
+            .init_resource::<WorldMap>()
+            .init_resource::<RemeshQueue>()
+            .init_resource::<ChunkTimings>()
+            .init_resource::<ChunkMaterials>()
+            .init_resource::<MeshScratch>()
+            .init_resource::<WorldOrigin>()
+            .init_resource::<GenerationHooks>()
+            .init_resource::<ChunkTasks>()
+            .init_resource::<ChunkStreaming>()
+            .init_resource::<SurfaceCache>()
+            .init_resource::<CursorGrabbed>()
+            .add_event::<BlockChanged>()
+            // The spawn height and preloaded chunks already need the configured terrain
+            .add_systems(
+                Startup,
+                (
+                    apply_terrain_settings.before(spawn_camera).before(preload_spawn_chunks),
+                    // The main menu may still change the seed
+                    preload_spawn_chunks.run_if(not(in_state(GameState::MainMenu))),
+                ),
+            )
+            .add_systems(OnExit(GameState::MainMenu), (place_player_at_spawn, preload_spawn_chunks).chain())
+            .add_systems(OnEnter(GameState::Playing), grab_cursor)
+            .add_systems(OnEnter(GameState::Paused), release_cursor)
+            // Already queued remeshes still finish while paused
+            .add_systems(
+                Update,
+                (
+                    toggle_pause,
+                    apply_chunk_surface,
+                    (
+                        apply_voxel_scale,
+                        rebase_origin,
+                        apply_terrain_settings,
+                        clear_surface_cache_on_change,
+                        update_chunk_streaming,
+                        generate_chunks,
+                        regenerate_player_chunk,
+                    )
+                        .chain()
+                        .run_if(not(in_state(GameState::Paused)))
+                        .run_if(not(in_state(GameState::MainMenu))),
+                    (
+                        remesh_on_style_change,
+                        update_chunk_lod,
+                        queue_block_changes,
+                        relight_block_changes,
+                        remesh_chunks,
+                        // Morphs must start from the meshes `remesh_chunks` just swapped in
+                        apply_deferred,
+                        animate_lod_morphs,
+                    )
+                        .chain(),
+                ),
+            );
+
+        // After everything reading input, so the click that re-grabs doesn't also break a block
+        #[cfg(not(feature = "headless"))]
+        app.add_systems(PostUpdate, handle_window_focus);
+    }
+}
+
+// Where the player's feet start, in absolute block coordinates
+fn spawn_point(settings: &Settings, world_gen: &WorldGen) -> Vec3 {
+    settings.spawn.unwrap_or_else(|| {
+        // Standing on the ground, or floating on the sea if the origin is underwater
+        let ground = world_gen.surface_height_at(0, 0).max(SEA_LEVEL);
+        Vec3::new(0.5, ground as f32, 0.5)
+    })
+}
+
+fn spawn_camera(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+) {
+    // Player
+    let feet = spawn_point(&settings, &world_gen);
+    let eye = (feet + Vec3::Y * EYE_HEIGHT - world_origin.block_offset().as_vec3()) * world_origin.voxel_scale;
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_translation(eye).looking_at(eye + Vec3::NEG_Z, Vec3::Y),
+            ..default()
+        },
+        above_water_fog(&settings),
+        Player,
+        PlayerPhysics::default(),
+        MovementMode::default(),
+        HeadBob::default(),
+        CameraMode::default(),
+        CameraPullBack::default(),
+    ));
+
+    // Light
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+
+    // Chunks around the player are generated by `generate_chunks` on the first frame
+}
+
+// Stands the player on the spawn of the world picked in the main menu, which
+// may have a different seed than the player was spawned with
+fn place_player_at_spawn(
+    settings: Res<Settings>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    surface_cache: Res<SurfaceCache>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    surface_cache.clear();
+    let feet = spawn_point(&settings, &world_gen);
+    let eye = (feet + Vec3::Y * EYE_HEIGHT - world_origin.block_offset().as_vec3()) * world_origin.voxel_scale;
+    for mut transform in player_query.iter_mut() {
+        transform.translation = eye;
+    }
+}
+
+// Underwater tint, shown while the camera is inside water
+#[cfg(not(feature = "headless"))]
+fn spawn_underwater_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.1, 0.25, 0.6, 0.35).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        UnderwaterOverlay,
+    ));
+}
+
+fn toggle_pause(
+    input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next_state.set(match state.get() {
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            GameState::MainMenu | GameState::Loading => return,
+        });
+    }
+}
+
+// Lock the cursor for mouse look; there is no window when running headless
+fn grab_cursor(
+    settings: Res<Settings>,
+    mut grabbed: ResMut<CursorGrabbed>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    grabbed.0 = true;
+    if let Ok(mut window) = windows.get_single_mut() {
+        lock_cursor(&mut window, settings.raw_mouse_input);
+    }
+}
+
+fn release_cursor(mut grabbed: ResMut<CursorGrabbed>, mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    grabbed.0 = false;
+    if let Ok(mut window) = windows.get_single_mut() {
+        unlock_cursor(&mut window);
+    }
+}
+
+fn lock_cursor(window: &mut Window, raw_mouse_input: bool) {
+    // A locked cursor stops reporting positions on some platforms
+    window.cursor.grab_mode = if raw_mouse_input { CursorGrabMode::Locked } else { CursorGrabMode::Confined };
+    window.cursor.visible = false;
+    // Start confined mouse look from the center instead of wherever the cursor was left
+    let center = Vec2::new(window.width(), window.height()) / 2.0;
+    window.set_cursor_position(Some(center));
+}
+
+fn unlock_cursor(window: &mut Window) {
+    window.cursor.grab_mode = CursorGrabMode::None;
+    window.cursor.visible = true;
+}
+
+// Lets go of the cursor when another window takes focus, since the motion
+// reported on return would spin the camera, and takes it back on a click
+#[cfg(not(feature = "headless"))]
+fn handle_window_focus(
+    mut focus_events: EventReader<WindowFocused>,
+    mouse: Res<Input<MouseButton>>,
+    state: Res<State<GameState>>,
+    settings: Res<Settings>,
+    mut grabbed: ResMut<CursorGrabbed>,
+    mut windows: Query<(Entity, &mut Window), With<PrimaryWindow>>,
+) {
+    let Ok((primary, mut window)) = windows.get_single_mut() else {
+        return;
+    };
+    let lost_focus = focus_events.read().any(|event| event.window == primary && !event.focused);
+    if lost_focus && grabbed.0 {
+        grabbed.0 = false;
+        unlock_cursor(&mut window);
+    } else if !grabbed.0
+        && window.focused
+        && *state.get() == GameState::Playing
+        && mouse.just_pressed(MouseButton::Left)
+    {
+        grabbed.0 = true;
+        lock_cursor(&mut window, settings.raw_mouse_input);
+    }
+}
+
+// Generates the chunks right around the spawn before the first frame, so the
+// player starts on solid ground; `generate_chunks` streams in the rest
+#[allow(clippy::too_many_arguments)]
+fn preload_spawn_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_materials: Res<ChunkMaterials>,
+    world_gen: Res<WorldGen>,
+    settings: Res<Settings>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut world_map: ResMut<WorldMap>,
+    mut timings: ResMut<ChunkTimings>,
+    mut scratch: ResMut<MeshScratch>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    mut sources: ChunkSources,
+    world_origin: Res<WorldOrigin>,
+) {
+    let spawn_chunk_pos = world_to_chunk(spawn_point(&settings, &world_gen).floor().as_ivec3());
+    let radius = SPAWN_PRELOAD_RADIUS.min(settings.initial_render_distance());
+    for x in -radius..=radius {
+        for z in -radius..=radius {
+            // Sampled on the first chunk of the column that isn't saved
+            let mut samples = None;
+            for y in world_gen.chunk_y_range() {
+                let position = IVec3::new(spawn_chunk_pos.x + x, y, spawn_chunk_pos.z + z);
+                let (chunk, modified) = match sources.world_save.load_chunk(position) {
+                    Some(saved) => (saved, true),
+                    None => {
+                        let columns =
+                            samples.get_or_insert_with(|| sources.surface_cache.column(&world_gen, position.xz()));
+                        let chunk = timed(&mut timings.generation, "generate", position, || {
+                            generate_chunk_in_column(&world_gen, &sources.hooks, position, columns)
+                        });
+                        (chunk, false)
+                    }
+                };
+                spawn_chunk(
+                    &mut commands,
+                    &mut meshes,
+                    &chunk_materials,
+                    &world_gen,
+                    &settings,
+                    &mut chunk_index,
+                    &mut world_map,
+                    &mut timings,
+                    &mut scratch,
+                    &mut remesh_queue,
+                    &world_origin,
+                    position,
+                    chunk,
+                    modified,
+                    ChunkLod::for_distance(&settings, x.abs().max(z.abs())),
+                );
+            }
+        }
+    }
+}
+
+// The generated chunks of a column, with how long generation took
+type ColumnTask = Task<(Vec<(IVec3, ChunkData)>, Duration)>;
+
+// Chunk columns being generated on the task pool, keyed by their xz
+#[derive(Resource, Default)]
+struct ChunkTasks {
+    tasks: HashMap<IVec2, ColumnTask>,
+}
+
+impl ChunkTasks {
+    // Drops the tasks of columns more than `distance` from `center`. A task
+    // that hasn't started never runs; one already generating still finishes
+    // on its thread, but its chunks are thrown away instead of meshed.
+    fn cancel_out_of_range(&mut self, center: IVec2, distance: i32) {
+        self.tasks.retain(|&column, _| (column - center).abs().max_element() <= distance);
+    }
+}
+
+// Where the voxels of a newly loaded chunk come from
+#[derive(SystemParam)]
+struct ChunkSources<'w> {
+    world_save: ResMut<'w, WorldSave>,
+    hooks: Res<'w, GenerationHooks>,
+    surface_cache: Res<'w, SurfaceCache>,
+    chunk_tasks: ResMut<'w, ChunkTasks>,
+}
+
+// How far chunks stream in this frame and how long they may take
+#[derive(Resource, Default)]
+pub struct ChunkStreaming {
+    // Columns in each horizontal direction to load; see `Settings::initial_render_distance`
+    pub radius: i32,
+    pub budget: Duration,
+    // Seconds played since the last loading screen
+    played: f32,
+}
+
+fn update_chunk_streaming(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    state: Res<State<GameState>>,
+    mut streaming: ResMut<ChunkStreaming>,
+) {
+    streaming.budget = match state.get() {
+        GameState::Loading => {
+            streaming.played = 0.0;
+            settings.loading_chunk_time_budget
+        }
+        GameState::Playing => {
+            streaming.played += time.delta_seconds();
+            settings.chunk_time_budget
+        }
+        GameState::MainMenu | GameState::Paused => settings.chunk_time_budget,
+    };
+    streaming.radius = settings.ramped_render_distance(streaming.played);
+}
+
+// Lower generates sooner: the column's ring distance, stretched up to
+// `BEHIND_PRIORITY` times for columns outside the view cone. `facing` is the
+// horizontal part of the view direction, so looking straight up or down,
+// where every side is about as visible, falls back to nearest first.
+fn column_priority(offset: IVec2, facing: Vec2) -> f32 {
+    let distance = offset.abs().max_element() as f32;
+    let alignment = offset.as_vec2().normalize_or_zero().dot(facing.normalize_or_zero());
+    // 0 anywhere inside the cone, 1 straight behind
+    let outside = ((VIEW_CONE_COS - alignment) / (VIEW_CONE_COS + 1.0)).max(0.0);
+    distance * (1.0 + (BEHIND_PRIORITY - 1.0) * outside * facing.length())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn generate_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_materials: Res<ChunkMaterials>,
+    world_gen: Res<WorldGen>,
+    settings: Res<Settings>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut world_map: ResMut<WorldMap>,
+    mut timings: ResMut<ChunkTimings>,
+    mut scratch: ResMut<MeshScratch>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    mut sources: ChunkSources,
+    world_origin: Res<WorldOrigin>,
+    streaming: Res<ChunkStreaming>,
+    player_query: Query<&Transform, With<Player>>,
+    chunk_query: Query<&Chunk>,
+    mesh_query: Query<&Handle<Mesh>>,
+) {
+    let player_transform = player_query.single();
+    let player_chunk = world_origin.chunk_at(player_transform.translation);
+    let render_distance = streaming.radius;
+    // Chunks within the full distance stay while the radius is still growing
+    let unload_distance = settings.render_distance + UNLOAD_MARGIN;
+    let facing = player_transform.forward().xz();
+    let start = Instant::now();
+    let budget = streaming.budget;
+
+    // Columns the player has left behind no longer take up task slots
+    sources.chunk_tasks.cancel_out_of_range(player_chunk.xz(), unload_distance);
+
+    // Bring in generated chunks. Columns left over once the budget runs out
+    // stay finished in the task map for next frame; at least one is taken so
+    // streaming never stalls.
+    let mut finished: Vec<(f32, IVec2)> = sources
+        .chunk_tasks
+        .tasks
+        .iter()
+        .filter(|(_, task)| task.is_finished())
+        .map(|(&column, _)| (column_priority(column - player_chunk.xz(), facing), column))
+        .collect();
+    finished.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    for (index, (_, column)) in finished.into_iter().enumerate() {
+        if index > 0 && start.elapsed() >= budget {
+            break;
+        }
+        let Some(task) = sources.chunk_tasks.tasks.remove(&column) else {
+            continue;
+        };
+        let (chunks, elapsed) = block_on(task);
+        let distance = (column - player_chunk.xz()).abs().max_element();
+        // Each chunk is credited an even share of its column's time
+        let share = elapsed / chunks.len().max(1) as u32;
+        for (position, chunk) in chunks {
+            record(&mut timings.generation, "generate", position, share);
+            if chunk_index.chunks.contains_key(&position) {
+                continue;
+            }
+            spawn_chunk(
+                &mut commands,
+                &mut meshes,
+                &chunk_materials,
+                &world_gen,
+                &settings,
+                &mut chunk_index,
+                &mut world_map,
+                &mut timings,
+                &mut scratch,
+                &mut remesh_queue,
+                &world_origin,
+                position,
+                chunk,
+                false,
+                ChunkLod::for_distance(&settings, distance),
+            );
+        }
+    }
+
+    // Start on missing chunks, nearest columns ahead of the player first, one
+    // task per column so its surface is only sampled once. Once the task limit
+    // is reached or the budget is spent, the rest are picked up on a later frame.
+    let mut columns: Vec<(f32, IVec2)> = (-render_distance..=render_distance)
+        .flat_map(|x| (-render_distance..=render_distance).map(move |z| IVec2::new(x, z)))
+        .map(|offset| (column_priority(offset, facing), offset))
+        .collect();
+    columns.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    let task_pool = AsyncComputeTaskPool::get();
+    for (_, offset) in columns {
+        // Saved chunks load right here, so stop before one could overrun
+        if start.elapsed() >= budget {
+            break;
+        }
+        let column = player_chunk.xz() + offset;
+        if sources.chunk_tasks.tasks.contains_key(&column) {
+            continue;
+        }
+        let mut missing = Vec::new();
+        // Top down, so the surface the player sees fills in first
+        for y in world_gen.chunk_y_range().rev() {
+            let chunk_pos = IVec3::new(column.x, y, column.y);
+            if chunk_index.chunks.contains_key(&chunk_pos) {
+                continue;
+            }
+
+            // Edited chunks come back from the save instead of being regenerated
+            if let Some(saved) = sources.world_save.load_chunk(chunk_pos) {
+                spawn_chunk(
+                    &mut commands,
+                    &mut meshes,
+                    &chunk_materials,
+                    &world_gen,
+                    &settings,
+                    &mut chunk_index,
+                    &mut world_map,
+                    &mut timings,
+                    &mut scratch,
+                    &mut remesh_queue,
+                    &world_origin,
+                    chunk_pos,
+                    saved,
+                    true,
+                    ChunkLod::for_distance(&settings, offset.abs().max_element()),
+                );
+            } else {
+                missing.push(chunk_pos);
+            }
+        }
+
+        if !missing.is_empty() && sources.chunk_tasks.tasks.len() < settings.max_concurrent_chunk_tasks {
+            let world_gen = world_gen.clone();
+            let hooks = sources.hooks.clone();
+            let surface_cache = sources.surface_cache.clone();
+            let task = task_pool.spawn(async move {
+                let start = Instant::now();
+                let chunks = generate_column(&world_gen, &hooks, &surface_cache, &missing);
+                (chunks, start.elapsed())
+            });
+            sources.chunk_tasks.tasks.insert(column, task);
+        }
+    }
+
+    // Time how long each chunk has been too far; one back in range is simply kept
+    for (&position, entry) in chunk_index.chunks.iter_mut() {
+        if (position - player_chunk).xz().abs().max_element() <= unload_distance {
+            entry.out_of_range_since = None;
+        } else if entry.out_of_range_since.is_none() {
+            entry.out_of_range_since = Some(start);
+        }
+    }
+
+    // Only drop far chunks once everything in range is in place, so a
+    // chunk never disappears before the ones replacing it can be drawn
+    let ready = (-render_distance..=render_distance).all(|x| {
+        (-render_distance..=render_distance).all(|z| {
+            world_gen
+                .chunk_y_range()
+                .all(|y| chunk_index.chunks.contains_key(&IVec3::new(player_chunk.x + x, y, player_chunk.z + z)))
+        })
+    });
+    if !ready {
+        return;
+    }
+
+    // Despawn chunks that have been too far for `Settings::chunk_despawn_delay`,
+    // nearest first so the order doesn't depend on query iteration. Chunks just
+    // past the edge are kept, so moving back and forth across a chunk border
+    // doesn't reload them.
+    let expired = |position: &IVec3| {
+        let since = chunk_index.chunks.get(position).and_then(|entry| entry.out_of_range_since);
+        since.is_some_and(|since| start.duration_since(since) >= settings.chunk_despawn_delay)
+    };
+    let mut far: Vec<IVec3> = chunk_query.iter().map(|chunk| chunk.position).filter(expired).collect();
+    far.sort_by_key(|position| ((*position - player_chunk).xz().abs().max_element(), position.to_array()));
+    for position in far {
+        unload_chunk(
+            &mut commands,
+            &mut chunk_index,
+            &mut world_map,
+            &mut sources.world_save,
+            &mesh_query,
+            position,
+        );
+    }
+}
+
+fn unload_chunk(
+    commands: &mut Commands,
+    chunk_index: &mut ChunkIndex,
+    world_map: &mut WorldMap,
+    world_save: &mut WorldSave,
+    mesh_query: &Query<&Handle<Mesh>>,
+    position: IVec3,
+) {
+    if let Some(entry) = chunk_index.chunks.remove(&position) {
+        // Keep edits so the chunk comes back as it was left
+        if let (true, Some(data)) = (entry.modified, world_map.chunks.get(&position)) {
+            world_save.store_chunk(position, data);
+        }
+        let entities = std::iter::once(entry.entity).chain(entry.submeshes.into_values());
+        let chunk_meshes = entities.filter_map(|entity| mesh_query.get(entity).ok()).map(Handle::id).collect();
+        despawn_with_meshes(commands, entry.entity, chunk_meshes);
+    }
+    world_map.chunks.remove(&position);
+}
+
+// Despawns every chunk and frees its meshes, then forgets all loaded voxels,
+// generation in flight, queued remeshes, pending edits and flowing water, so
+// the next world starts from nothing. Edits not yet saved are dropped; save
+// first to keep them. Runs as a system or as a command.
+pub fn reset_world(world: &mut World) {
+    let mut chunk_query = world.query_filtered::<(Entity, Option<&Children>), With<Chunk>>();
+    let mut roots = Vec::new();
+    let mut chunk_meshes = Vec::new();
+    for (entity, children) in chunk_query.iter(world) {
+        roots.push(entity);
+        let entities = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+        chunk_meshes.extend(entities.filter_map(|entity| world.get::<Handle<Mesh>>(entity)).map(Handle::id));
+    }
+    for entity in roots {
+        world.entity_mut(entity).despawn_recursive();
+    }
+    let mut meshes = world.resource_mut::<Assets<Mesh>>();
+    for mesh in chunk_meshes {
+        meshes.remove(mesh);
+    }
+
+    world.resource_mut::<ChunkIndex>().chunks.clear();
+    world.resource_mut::<WorldMap>().chunks.clear();
+    world.resource_mut::<ChunkTasks>().tasks.clear();
+    world.resource_mut::<RemeshQueue>().chunks.clear();
+    // Both belong to plugins a bare world may not have
+    if let Some(mut pending) = world.get_resource_mut::<fill::PendingEdits>() {
+        pending.clear();
+    }
+    if let Some(mut fluid) = world.get_resource_mut::<FluidSim>() {
+        fluid.clear();
+    }
+}
+
+// Despawns an entity with its children and frees their meshes in a single
+// command, freeing them now rather than relying on no other handle being
+// alive, without a moment where a live entity points at a removed mesh
+fn despawn_with_meshes(commands: &mut Commands, entity: Entity, meshes: Vec<AssetId<Mesh>>) {
+    commands.add(move |world: &mut World| {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+        let mut assets = world.resource_mut::<Assets<Mesh>>();
+        for mesh in meshes {
+            assets.remove(mesh);
+        }
+    });
+}
+
+// Points an entity at a newly added mesh and frees the one it had, swapped in
+// a single command so the entity never holds a handle to a removed asset
+fn swap_mesh(commands: &mut Commands, meshes: &mut Assets<Mesh>, entity: Entity, old: &Handle<Mesh>, mesh: Mesh) {
+    let (new, old) = (meshes.add(mesh), old.id());
+    commands.add(move |world: &mut World| {
+        // A chunk unloaded meanwhile drops the new handle, and its mesh with it
+        if let Some(mut entity) = world.get_entity_mut(entity) {
+            entity.insert(new);
+        }
+        world.resource_mut::<Assets<Mesh>>().remove(old);
+    });
+}
+
+// F6 throws away the chunk the player is in, edits and all, and generates it
+// again from the current `WorldGen`, skipping the surface cache, so generator
+// tweaks can be checked without reloading the world
+#[allow(clippy::too_many_arguments)]
+fn regenerate_player_chunk(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_materials: Res<ChunkMaterials>,
+    world_gen: Res<WorldGen>,
+    settings: Res<Settings>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut world_map: ResMut<WorldMap>,
+    mut timings: ResMut<ChunkTimings>,
+    mut scratch: ResMut<MeshScratch>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    mut sources: ChunkSources,
+    world_origin: Res<WorldOrigin>,
+    player_query: Query<&Transform, With<Player>>,
+    mesh_query: Query<&Handle<Mesh>>,
+) {
+    if !input.just_pressed(KeyCode::F6) {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let position = world_origin.chunk_at(player_transform.translation);
+    let Some(entry) = chunk_index.chunks.get_mut(&position) else {
+        return;
+    };
+    entry.modified = false;
+    sources.world_save.discard_chunk(position);
+    unload_chunk(
+        &mut commands,
+        &mut chunk_index,
+        &mut world_map,
+        &mut sources.world_save,
+        &mesh_query,
+        position,
+    );
+
+    let generated = generate_column(&world_gen, &sources.hooks, &SurfaceCache::default(), &[position]);
+    for (position, chunk) in generated {
+        spawn_chunk(
+            &mut commands,
+            &mut meshes,
+            &chunk_materials,
+            &world_gen,
+            &settings,
+            &mut chunk_index,
+            &mut world_map,
+            &mut timings,
+            &mut scratch,
+            &mut remesh_queue,
+            &world_origin,
+            position,
+            chunk,
+            false,
+            ChunkLod::for_distance(&settings, 0),
+        );
+    }
+    // Neighbors may show or hide faces against the new voxels
+    for offset in CUBE_FACES {
+        remesh_queue.chunks.insert(position + offset);
+    }
+    info!("Regenerated chunk {position}");
+}
+
+// Rebuilds the generator when `Settings::noise`, `voxel_scale` or the world
+// bounds change, then drops every unedited chunk so it streams back in with
+// the new terrain
+#[allow(clippy::too_many_arguments)]
+fn apply_terrain_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut world_gen: ResMut<WorldGen>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut world_map: ResMut<WorldMap>,
+    mut sources: ChunkSources,
+    mesh_query: Query<&Handle<Mesh>>,
+) {
+    let voxel_scale = settings.voxel_scale as f64;
+    let bounds = (settings.world_bottom, settings.world_top.max(settings.world_bottom));
+    let current = (world_gen.noise, world_gen.voxel_scale, (world_gen.bottom_y, world_gen.top_y));
+    if current == (settings.noise, voxel_scale, bounds) {
+        return;
+    }
+    let rebuilt = WorldGen { voxel_scale, ..WorldGen::with_noise(world_gen.seed, settings.noise) };
+    *world_gen = rebuilt.with_bounds(bounds.0, bounds.1);
+    // Whatever is still generating has the old terrain
+    sources.chunk_tasks.tasks.clear();
+
+    let unedited: Vec<IVec3> =
+        chunk_index.chunks.iter().filter(|(_, entry)| !entry.modified).map(|(&position, _)| position).collect();
+    for position in unedited {
+        unload_chunk(
+            &mut commands,
+            &mut chunk_index,
+            &mut world_map,
+            &mut sources.world_save,
+            &mesh_query,
+            position,
+        );
+    }
+}
+
+#[inline]
+fn voxel_index(x: i32, y: i32, z: i32) -> usize {
+    (x + CHUNK_SIZE * (y + CHUNK_SIZE * z)) as usize
+}
+
+#[inline]
+fn column_index(x: i32, z: i32) -> usize {
+    (x + CHUNK_SIZE * z) as usize
+}
+
+// Density of a voxel whose block was set outright rather than generated.
+// Only opaque terrain is smoothed; other solid blocks keep their cubes.
+fn block_density(block: BlockType) -> f32 {
+    // Slabs are left to the blocky pass, which draws them at their own height
+    if block.is_solid() && block.fills_voxel() && block.material_group() == MaterialGroup::Opaque {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+pub struct ChunkData {
+    voxels: Vec<BlockType>,
+    // Signed distance-like field at each voxel center, positive inside solid
+    // ground, for the smooth mesher. Edits snap it to the new block.
+    density: Vec<f32>,
+    // Sky light in the high nibble and block light in the low one, filled in
+    // by `lighting` once the chunk is in the world
+    light: Vec<u8>,
+}
+
+impl Default for ChunkData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkData {
+    // A chunk of `CHUNK_SIZE`³ air, unlit
+    pub fn new() -> Self {
+        Self {
+            voxels: vec![BlockType::Air; (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize],
+            density: vec![-1.0; (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize],
+            light: vec![0; (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize],
+        }
+    }
+
+    // Local coordinates in storage order, so walking them touches memory linearly
+    fn iter_coords() -> impl Iterator<Item = IVec3> {
+        (0..CHUNK_SIZE).flat_map(|z| {
+            (0..CHUNK_SIZE).flat_map(move |y| (0..CHUNK_SIZE).map(move |x| IVec3::new(x, y, z)))
+        })
+    }
+
+    pub fn get(&self, local: IVec3) -> BlockType {
+        self.voxels[voxel_index(local.x, local.y, local.z)]
+    }
+
+    pub fn set(&mut self, local: IVec3, block: BlockType) {
+        let index = voxel_index(local.x, local.y, local.z);
+        self.voxels[index] = block;
+        self.density[index] = block_density(block);
+    }
+
+    pub fn is_solid(&self, local: IVec3) -> bool {
+        self.get(local).is_solid()
+    }
+
+    pub fn is_all_air(&self) -> bool {
+        self.voxels.iter().all(|&block| block == BlockType::Air)
+    }
+
+    pub fn is_full_solid(&self) -> bool {
+        self.voxels.iter().all(|block| block.is_solid())
+    }
+
+    pub fn density(&self, local: IVec3) -> f32 {
+        self.density[voxel_index(local.x, local.y, local.z)]
+    }
+
+    // For chunks loaded without a density field, e.g. from a save
+    fn density_from_voxels(&mut self) {
+        for (density, &block) in self.density.iter_mut().zip(&self.voxels) {
+            *density = block_density(block);
+        }
+    }
+
+    fn light(&self, local: IVec3, channel: LightChannel) -> u8 {
+        let packed = self.light[voxel_index(local.x, local.y, local.z)];
+        match channel {
+            LightChannel::Sky => packed >> 4,
+            LightChannel::Block => packed & 0x0f,
+        }
+    }
+
+    fn set_light(&mut self, local: IVec3, channel: LightChannel, level: u8) {
+        let packed = &mut self.light[voxel_index(local.x, local.y, local.z)];
+        *packed = match channel {
+            LightChannel::Sky => (*packed & 0x0f) | (level << 4),
+            LightChannel::Block => (*packed & 0xf0) | level,
+        };
+    }
+}
+
+// Surface, solid height and whether it is beach for each block column of a
+// chunk column, indexed by `column_index`. None of it depends on y, so it is
+// sampled once and shared by every chunk stacked in the column.
+// `SurfaceCache` keeps them for columns generated again later.
+type ColumnSamples = Vec<(f64, i32, bool)>;
+
+fn sample_column(world_gen: &WorldGen, column: IVec2) -> ColumnSamples {
+    let origin = column * CHUNK_SIZE;
+    let mut columns = vec![(0.0, 0, false); (CHUNK_SIZE * CHUNK_SIZE) as usize];
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let surface = world_gen.surface_at(origin.x + x, origin.y + z);
+            let height = solid_height(surface);
+            columns[column_index(x, z)] = (surface, height, world_gen.is_beach(origin.x + x, origin.y + z, height));
+        }
+    }
+    columns
+}
+
+// Generates chunks stacked in one column, sampling the surface only once
+fn generate_column(
+    world_gen: &WorldGen,
+    hooks: &GenerationHooks,
+    surface_cache: &SurfaceCache,
+    positions: &[IVec3],
+) -> Vec<(IVec3, ChunkData)> {
+    let Some(first) = positions.first() else {
+        return Vec::new();
+    };
+    let columns = surface_cache.column(world_gen, first.xz());
+    positions
+        .iter()
+        .map(|&position| (position, generate_chunk_in_column(world_gen, hooks, position, &columns)))
+        .collect()
+}
+
+fn generate_chunk_in_column(
+    world_gen: &WorldGen,
+    hooks: &GenerationHooks,
+    position: IVec3,
+    columns: &ColumnSamples,
+) -> ChunkData {
+    let mut chunk = ChunkData::new();
+    let origin = chunk_to_world_origin(position);
+
+    // Density first; the blocks are then read off its sign
+    for (density, local) in chunk.density.iter_mut().zip(ChunkData::iter_coords()) {
+        let (surface, _, _) = columns[column_index(local.x, local.z)];
+        *density = world_gen.column_density(origin.y + local.y, surface);
+    }
+    for ((voxel, &density), local) in chunk.voxels.iter_mut().zip(&chunk.density).zip(ChunkData::iter_coords()) {
+        let (_, height, beach) = columns[column_index(local.x, local.z)];
+        *voxel = world_gen.block_for_density(origin.y + local.y, density, height, beach);
+    }
+
+    // Ore pass: scatter ores through the stone
+    for (voxel, local) in chunk.voxels.iter_mut().zip(ChunkData::iter_coords()) {
+        if *voxel == BlockType::Stone {
+            if let Some(ore) = world_gen.ore_at(origin + local) {
+                *voxel = ore;
+            }
+        }
+    }
+
+    hooks.run(position, &mut chunk, world_gen);
+    chunk
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_chunk(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    chunk_materials: &ChunkMaterials,
+    world_gen: &WorldGen,
+    settings: &Settings,
+    chunk_index: &mut ChunkIndex,
+    world_map: &mut WorldMap,
+    timings: &mut ChunkTimings,
+    scratch: &mut MeshScratch,
+    remesh_queue: &mut RemeshQueue,
+    world_origin: &WorldOrigin,
+    position: IVec3,
+    chunk: ChunkData,
+    modified: bool,
+    lod: ChunkLod,
+) {
+    // The index owns the one entity per position; a chunk already in it keeps
+    // its voxels and entity, whichever path tries to spawn it again
+    if chunk_index.chunks.contains_key(&position) {
+        return;
+    }
+    let origin = chunk_to_world_origin(position);
+    world_map.chunks.insert(position, chunk);
+    // Neighbors lit through this chunk need their baked light refreshed
+    remesh_queue.chunks.extend(light_new_chunk(world_map, world_gen, position));
+
+    let chunk = &world_map.chunks[&position];
+    timed(&mut timings.meshing, "mesh", position, || {
+        let (shading, tints, style) = (settings.face_shading, &settings.biome_tints, settings.mesh_style);
+        let neighbors = NeighborSnapshot::capture(world_map, position);
+        build_chunk_meshes(chunk, position, world_gen, &neighbors, scratch, shading, tints, style, lod)
+    });
+    let empty = scratch.is_empty();
+
+    // No draw order of our own: Bevy sorts the opaque pass front to back by
+    // the view depth of each mesh's translation, here the chunk's low corner,
+    // so near chunks already fill the depth buffer before far ones are drawn.
+    // Submeshes share their parent's translation and sort alongside it; the
+    // `the_opaque_pass_draws_near_chunks_first` test holds Bevy to this.
+    let mut chunk_entity = commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(scratch.builder(MaterialGroup::Opaque).build(settings.mesh_topology)),
+            material: chunk_materials.get(MaterialGroup::Opaque),
+            transform: Transform::from_translation(world_origin.to_render(origin))
+                .with_scale(Vec3::splat(world_origin.voxel_scale)),
+            ..default()
+        },
+        Chunk { position },
+        scratch.stats(),
+    ));
+
+    let mut submeshes = HashMap::new();
+    chunk_entity.with_children(|parent| {
+        for group in MaterialGroup::ALL.into_iter().skip(1) {
+            let builder = scratch.builder(group);
+            if !builder.vertices.is_empty() {
+                let submesh = spawn_submesh(parent, meshes, chunk_materials, group, builder, settings.mesh_topology);
+                submeshes.insert(group, submesh);
+            }
+        }
+    });
+    let entity = chunk_entity.id();
+
+    let entry = ChunkEntry { entity, submeshes, empty, modified, lod, out_of_range_since: None };
+    chunk_index.chunks.insert(position, entry);
+}
+
+fn spawn_submesh(
+    parent: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    chunk_materials: &ChunkMaterials,
+    group: MaterialGroup,
+    builder: &ChunkMeshBuilder,
+    topology: MeshTopology,
+) -> Entity {
+    parent
+        .spawn(PbrBundle {
+            mesh: meshes.add(builder.build(topology)),
+            material: chunk_materials.get(group),
+            ..default()
+        })
+        .id()
+}
+
+// Fills the scratch builders with the geometry of a chunk, one per material
+// group. At a coarser LOD each cell of voxels becomes one larger block; the
+// cubes stay closed, so they meet full-detail neighbors without cracks.
+// Everything outside the chunk comes from `neighbors` or the generator, so
+// this can run off the main thread.
+#[allow(clippy::too_many_arguments)]
+fn build_chunk_meshes(
+    chunk: &ChunkData,
+    position: IVec3,
+    world_gen: &WorldGen,
+    neighbors: &NeighborSnapshot,
+    scratch: &mut MeshScratch,
+    shading: FaceShading,
+    tints: &BiomeTints,
+    style: MeshStyle,
+    lod: ChunkLod,
+) {
+    let step = lod.step();
+    let origin = chunk_to_world_origin(position);
+    for builder in &mut scratch.builders {
+        builder.reset(shading);
+    }
+    // Nothing to draw; open sky above the terrain is most chunks
+    if chunk.is_all_air() {
+        return;
+    }
+    let neighbor = |local: IVec3| {
+        if local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all() {
+            chunk.get(local)
+        } else {
+            neighbors.get(local).map_or_else(|| world_gen.voxel_at(origin + local), |voxel| voxel.block)
+        }
+    };
+    let light = |local: IVec3| {
+        let level = if local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all() {
+            chunk.light(local, LightChannel::Sky).max(chunk.light(local, LightChannel::Block))
+        } else {
+            neighbors.light(local)
+        };
+        light_brightness(level)
+    };
+    // Grass and terrain tints, worked out once per column
+    let biome_colors = RefCell::new(HashMap::new());
+    let column_colors = |local: IVec3| {
+        *biome_colors.borrow_mut().entry(local.xz()).or_insert_with(|| {
+            let world = origin + local;
+            blended_biome_colors(world_gen, tints, world.x, world.z)
+        })
+    };
+    let foliage = |local: IVec3| column_colors(local).0;
+    let base_color = |local: IVec3, block: BlockType| {
+        if block.is_biome_tinted() {
+            let (color, tint) = (block.color().as_rgba_f32(), column_colors(local).1.as_rgba_f32());
+            Color::rgba(color[0] * tint[0], color[1] * tint[1], color[2] * tint[2], color[3])
+        } else {
+            block.color()
+        }
+    };
+
+    let smooth = style == MeshStyle::Smooth;
+    if smooth {
+        let density = |local: IVec3| {
+            if local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all() {
+                chunk.density(local)
+            } else {
+                neighbors.get(local).map_or_else(|| world_gen.density_at(origin + local), |voxel| voxel.density)
+            }
+        };
+        let color = |local: IVec3, block: BlockType| match block {
+            BlockType::Grass => foliage(local),
+            _ => base_color(local, block),
+        };
+        add_smooth_surface(scratch.builder_mut(MaterialGroup::Opaque), step, density, neighbor, color, light);
+    }
+    // Light in front of each face of a cell, taken just past its low corner
+    let past_face = |dir: IVec3| dir.max(IVec3::ZERO) * step + dir.min(IVec3::ZERO);
+    let face_light = |local: IVec3| CUBE_FACES.map(|dir| light(local + past_face(dir)));
+    // Which faces of a cube can be seen past the blocks beside it
+    let shown_faces = |local: IVec3, block: BlockType| {
+        CUBE_FACES.map(|dir| block.shows_face_against(neighbor(local + past_face(dir))))
+    };
+
+    let cells = ChunkData::iter_coords()
+        .filter(|local| (*local % step).cmpeq(IVec3::ZERO).all())
+        .map(|local| (local, coarse_block(chunk, local, step)));
+    for (local, block) in cells {
+        let IVec3 { x, y, z } = local;
+        // Already covered by the smooth surface
+        if smooth && block_density(block) > 0.0 {
+            continue;
+        }
+        match block {
+            BlockType::Air => {}
+            BlockType::Grass => {
+                let color = foliage(local);
+                let builder = scratch.builder_mut(MaterialGroup::Opaque);
+                let base = base_color(local, block);
+                builder.add_cube(x, y, z, step, 1.0, block, base, color, face_light(local), shown_faces(local, block));
+            }
+            block if block.is_water() => {
+                let above = neighbor(local + IVec3::Y * step);
+                // Flowing water sits a little lower unless more water pours onto it
+                let fill = if block == BlockType::Water || above.is_water() { 1.0 } else { FLOWING_WATER_HEIGHT };
+                let (fx, fy, fz, size) = (x as f32, y as f32, z as f32, step as f32);
+                let height = fill * size;
+                let water_builder = scratch.builder_mut(MaterialGroup::Water);
+                if block.shows_face_against(above) {
+                    let top = fy + height;
+                    water_builder.add_face(
+                        [[fx, top, fz], [fx, top, fz + size], [fx + size, top, fz + size], [fx + size, top, fz]],
+                        [0.0, 1.0, 0.0],
+                        block.color(),
+                        light(local + IVec3::Y * step),
+                        block.face_texture(Facing::Up),
+                    );
+                }
+                let sides = water_side_faces(fx, fy, fz, size, height).into_iter().zip(Facing::ALL);
+                for ((dir, corners, normal), face) in sides {
+                    let offset = past_face(dir);
+                    if block.shows_face_against(neighbor(local + offset)) {
+                        let texture = block.face_texture(face);
+                        water_builder.add_face(corners, normal, block.color(), light(local + offset), texture);
+                    }
+                }
+            }
+            _ => {
+                // Emissive blocks are at least as bright as their own glow
+                let glow = light_brightness(block.emission());
+                let color = base_color(local, block);
+                let mut shown = shown_faces(local, block);
+                let mut height = 1.0;
+                // A slab is drawn as tall as it collides, with its top inside
+                // the voxel where nothing above can hide it
+                if !block.fills_voxel() {
+                    height = block.collision_shape().iter().fold(0.0, |top: f32, part| top.max(part.max.y));
+                    shown[Facing::Up.index() as usize] = true;
+                }
+                scratch.builder_mut(block.material_group()).add_cube(
+                    x,
+                    y,
+                    z,
+                    step,
+                    height,
+                    block,
+                    color,
+                    color,
+                    face_light(local).map(|light| light.max(glow)),
+                    shown,
+                );
+            }
+        }
+    }
+}
+
+// The four vertical faces of a `size`-wide water block of the given height,
+// with the direction each one faces
+fn water_side_faces(x: f32, y: f32, z: f32, size: f32, height: f32) -> [(IVec3, [[f32; 3]; 4], [f32; 3]); 4] {
+    let top = y + height;
+    let (x1, z1) = (x + size, z + size);
+    [
+        (IVec3::NEG_Z, [[x, y, z], [x, top, z], [x1, top, z], [x1, y, z]], [0.0, 0.0, -1.0]),
+        (IVec3::Z, [[x, y, z1], [x1, y, z1], [x1, top, z1], [x, top, z1]], [0.0, 0.0, 1.0]),
+        (IVec3::NEG_X, [[x, y, z], [x, y, z1], [x, top, z1], [x, top, z]], [-1.0, 0.0, 0.0]),
+        (IVec3::X, [[x1, y, z], [x1, top, z], [x1, top, z1], [x1, y, z1]], [1.0, 0.0, 0.0]),
+    ]
+}
+
+fn queue_block_changes(
+    mut events: EventReader<BlockChanged>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+) {
+    for event in events.read() {
+        let chunk_pos = world_to_chunk(event.world_pos);
+        if let Some(entry) = chunk_index.chunks.get_mut(&chunk_pos) {
+            entry.modified = true;
+        }
+        remesh_queue.queue_voxel(event.world_pos);
+    }
+}
+
+// Switching mesh style, topology or biome tints rebuilds every loaded chunk
+fn remesh_on_style_change(
+    settings: Res<Settings>,
+    chunk_index: Res<ChunkIndex>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    mut last_style: Local<Option<(MeshStyle, MeshTopology, BiomeTints)>>,
+) {
+    let style = (settings.mesh_style, settings.mesh_topology, settings.biome_tints);
+    if last_style.is_some_and(|last| last != style) {
+        remesh_queue.chunks.extend(chunk_index.chunks.keys().copied());
+    }
+    *last_style = Some(style);
+}
+
+// Runs after every edit system, so a chunk edited many times in a frame is
+// still rebuilt once; the queue is a set and drains here
+#[allow(clippy::too_many_arguments)]
+fn remesh_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_materials: Res<ChunkMaterials>,
+    world_gen: Res<WorldGen>,
+    settings: Res<Settings>,
+    world_map: Res<WorldMap>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut timings: ResMut<ChunkTimings>,
+    mut scratch: ResMut<MeshScratch>,
+    mesh_query: Query<&Handle<Mesh>>,
+    mut morph_query: Query<&mut LodMorph>,
+) {
+    for position in remesh_queue.chunks.drain() {
+        let (Some(chunk), Some(entry)) = (world_map.chunks.get(&position), chunk_index.chunks.get_mut(&position)) else {
+            continue;
+        };
+        timed(&mut timings.meshing, "remesh", position, || {
+            let (shading, tints, style) = (settings.face_shading, &settings.biome_tints, settings.mesh_style);
+            let neighbors = NeighborSnapshot::capture(&world_map, position);
+            build_chunk_meshes(chunk, position, &world_gen, &neighbors, &mut scratch, shading, tints, style, entry.lod)
+        });
+        entry.empty = scratch.is_empty();
+        timings.remeshes += 1;
+        commands.entity(entry.entity).insert(scratch.stats());
+        if let Ok(mut morph) = morph_query.get_mut(entry.entity) {
+            if !morph.remeshed(settings.mesh_style) {
+                commands.entity(entry.entity).remove::<LodMorph>();
+            }
+        }
+
+        if let Ok(mesh) = mesh_query.get(entry.entity) {
+            let built = scratch.builder(MaterialGroup::Opaque).build(settings.mesh_topology);
+            swap_mesh(&mut commands, &mut meshes, entry.entity, mesh, built);
+        }
+
+        for group in MaterialGroup::ALL.into_iter().skip(1) {
+            let builder = scratch.builder(group);
+            match (entry.submeshes.get(&group).copied(), builder.vertices.is_empty()) {
+                (Some(submesh), false) => {
+                    if let Ok(mesh) = mesh_query.get(submesh) {
+                        swap_mesh(&mut commands, &mut meshes, submesh, mesh, builder.build(settings.mesh_topology));
+                    }
+                }
+                (Some(submesh), true) => {
+                    let mesh = mesh_query.get(submesh).ok().map(Handle::id);
+                    despawn_with_meshes(&mut commands, submesh, mesh.into_iter().collect());
+                    entry.submeshes.remove(&group);
+                }
+                (None, false) => {
+                    commands.entity(entry.entity).with_children(|parent| {
+                        let topology = settings.mesh_topology;
+                        let submesh = spawn_submesh(parent, &mut meshes, &chunk_materials, group, builder, topology);
+                        entry.submeshes.insert(group, submesh);
+                    });
+                }
+                (None, true) => {}
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn apply_camera_clipping(
+    settings: Res<Settings>,
+    world_gen: Res<WorldGen>,
+    mut camera_query: Query<&mut Projection, With<Player>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut projection in camera_query.iter_mut() {
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.near = settings.near;
+            perspective.far = settings.far_plane((world_gen.top_y - world_gen.bottom_y + 1) as f32);
+        }
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn apply_msaa(settings: Res<Settings>, mut msaa: ResMut<Msaa>) {
+    if settings.is_changed() && *msaa != settings.msaa() {
+        *msaa = settings.msaa();
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn underwater_effect(
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    world_map: Res<WorldMap>,
+    settings: Res<Settings>,
+    mut was_underwater: Local<bool>,
+    mut camera_query: Query<(&Transform, &mut FogSettings), With<Player>>,
+    mut overlay_query: Query<&mut Visibility, With<UnderwaterOverlay>>,
+) {
+    let Ok((transform, mut fog)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let eye = world_origin.voxel_at(transform.translation);
+    let underwater = world_map.voxel(eye).unwrap_or_else(|| world_gen.voxel_at(eye)).is_water();
+    if underwater == *was_underwater && !settings.is_changed() {
+        return;
+    }
+    *was_underwater = underwater;
+
+    *fog = if underwater { underwater_fog() } else { above_water_fog(&settings) };
+    for mut visibility in overlay_query.iter_mut() {
+        *visibility = if underwater { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
+
+fn above_water_fog(settings: &Settings) -> FogSettings {
+    let distance = (settings.render_distance * CHUNK_SIZE) as f32 * settings.voxel_scale;
+    FogSettings {
+        color: Color::rgb(0.6, 0.7, 0.8),
+        falloff: FogFalloff::Linear {
+            start: distance * 0.6,
+            end: distance,
+        },
+        ..default()
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn underwater_fog() -> FogSettings {
+    FogSettings {
+        color: Color::rgb(0.1, 0.25, 0.5),
+        falloff: FogFalloff::Exponential { density: 0.15 },
+        ..default()
+    }
+}
+
+// Raw temperature and humidity noise at a column, each roughly in -1..1
+fn climate_at(temperature: &Noise2d, humidity: &Noise2d, world_x: i32, world_z: i32) -> (f64, f64) {
+    let point = [world_x as f64 * BIOME_SCALE, world_z as f64 * BIOME_SCALE];
+    (temperature.get(point), humidity.get(point))
+}
+
+// Sand reaches this many blocks above sea level; wider in hot, dry biomes
+fn beach_width(biome: Biome) -> i32 {
+    match biome {
+        Biome::Desert => 4,
+        Biome::Savanna | Biome::Plains => 2,
+        Biome::Forest | Biome::Taiga | Biome::Tundra => 1,
+    }
+}
+
+fn foliage_color(biome: Biome) -> Color {
+    match biome {
+        Biome::Desert => Color::rgb(0.75, 0.7, 0.4),
+        Biome::Savanna => Color::rgb(0.6, 0.65, 0.3),
+        Biome::Plains => Color::rgb(0.45, 0.7, 0.3),
+        Biome::Forest => Color::rgb(0.25, 0.55, 0.2),
+        Biome::Taiga => Color::rgb(0.3, 0.5, 0.4),
+        Biome::Tundra => Color::rgb(0.6, 0.7, 0.65),
+    }
+}
+
+// Averages the foliage color and terrain tint over the surrounding columns
+// so both fade across biome borders instead of switching abruptly.
+fn blended_biome_colors(world_gen: &WorldGen, tints: &BiomeTints, world_x: i32, world_z: i32) -> (Color, Color) {
+    let (mut foliage, mut tint) = (Vec4::ZERO, Vec4::ZERO);
+    let mut samples = 0.0;
+
+    for dx in (-BIOME_BLEND_RADIUS..=BIOME_BLEND_RADIUS).step_by(2) {
+        for dz in (-BIOME_BLEND_RADIUS..=BIOME_BLEND_RADIUS).step_by(2) {
+            let biome = world_gen.biome_at(world_x + dx, world_z + dz);
+            foliage += Vec4::from(foliage_color(biome).as_rgba_f32());
+            tint += Vec4::from(tints.get(biome).as_rgba_f32());
+            samples += 1.0;
+        }
+    }
+
+    let [foliage, tint] = [foliage / samples, tint / samples];
+    (Color::rgba(foliage.x, foliage.y, foliage.z, foliage.w), Color::rgba(tint.x, tint.y, tint.z, tint.w))
+}
+
+// Directions of the cube faces emitted by `ChunkMeshBuilder::add_cube`
+const CUBE_FACES: [IVec3; 6] = [IVec3::NEG_Z, IVec3::Z, IVec3::NEG_X, IVec3::X, IVec3::Y, IVec3::NEG_Y];
+
+// Mesh builders reused across chunks so their buffers keep their capacity
+// instead of being reallocated for every chunk and remesh
+#[derive(Resource, Default)]
+struct MeshScratch {
+    // One per material group, indexed by `MaterialGroup::index`
+    builders: [ChunkMeshBuilder; MaterialGroup::COUNT],
+}
+
+impl MeshScratch {
+    fn builder(&self, group: MaterialGroup) -> &ChunkMeshBuilder {
+        &self.builders[group.index()]
+    }
+
+    fn builder_mut(&mut self, group: MaterialGroup) -> &mut ChunkMeshBuilder {
+        &mut self.builders[group.index()]
+    }
+
+    fn is_empty(&self) -> bool {
+        self.builders.iter().all(|builder| builder.vertices.is_empty())
+    }
+
+    fn stats(&self) -> ChunkMeshStats {
+        ChunkMeshStats {
+            vertices: self.builders.iter().map(|builder| builder.vertices.len()).sum(),
+            indices: self.builders.iter().map(|builder| builder.indices.len()).sum(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ChunkMeshBuilder {
+    vertices: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+    normals: Vec<[f32; 3]>,
+    colors: Vec<[f32; 4]>,
+    uvs: Vec<[f32; 2]>,
+    tiles: Vec<u32>,
+    shading: FaceShading,
+}
+
+impl ChunkMeshBuilder {
+    // Empties the buffers without freeing them
+    fn reset(&mut self, shading: FaceShading) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.normals.clear();
+        self.colors.clear();
+        self.uvs.clear();
+        self.tiles.clear();
+        self.shading = shading;
+    }
+
+    // A cube of `block` `size` blocks wide from its low corner, cut to
+    // `height` of that size. `light` holds the brightness in front of each
+    // face and `shown` whether it's drawn at all, both in `CUBE_FACES` order.
+    #[allow(clippy::too_many_arguments)]
+    fn add_cube(
+        &mut self,
+        x: i32,
+        y: i32,
+        z: i32,
+        size: i32,
+        height: f32,
+        block: BlockType,
+        color: Color,
+        top_color: Color,
+        light: [f32; 6],
+        shown: [bool; 6],
+    ) {
+        let x = x as f32;
+        let y = y as f32;
+        let z = z as f32;
+        // The far corner, keeping the face table below readable
+        let (x1, y1, z1) = (x + size as f32, y + height * size as f32, z + size as f32);
+
+        // Each face gets its own four vertices so it can carry its own normal and color
+        let faces: [([[f32; 3]; 4], [f32; 3]); 6] = [
+            ([[x, y, z], [x, y1, z], [x1, y1, z], [x1, y, z]], [0.0, 0.0, -1.0]), // Front
+            ([[x, y, z1], [x1, y, z1], [x1, y1, z1], [x, y1, z1]], [0.0, 0.0, 1.0]), // Back
+            ([[x, y, z], [x, y, z1], [x, y1, z1], [x, y1, z]], [-1.0, 0.0, 0.0]), // Left
+            ([[x1, y, z], [x1, y1, z], [x1, y1, z1], [x1, y, z1]], [1.0, 0.0, 0.0]), // Right
+            ([[x, y1, z], [x, y1, z1], [x1, y1, z1], [x1, y1, z]], [0.0, 1.0, 0.0]), // Top
+            ([[x, y, z], [x1, y, z], [x1, y, z1], [x, y, z1]], [0.0, -1.0, 0.0]), // Bottom
+        ];
+
+        for (((corners, normal), light), face) in faces.into_iter().zip(light).zip(Facing::ALL) {
+            if !shown[face.index() as usize] {
+                continue;
+            }
+            let color = if normal[1] > 0.0 { top_color } else { color };
+            self.add_face(corners, normal, color, light, block.face_texture(face));
+        }
+    }
+
+    fn add_face(&mut self, corners: [[f32; 3]; 4], normal: [f32; 3], color: Color, light: f32, texture: FaceTexture) {
+        let v_index = self.vertices.len() as u32;
+        let brightness = self.shading.brightness(normal) * light;
+        let [r, g, b, a] = color.as_linear_rgba_f32();
+
+        self.vertices.extend_from_slice(&corners);
+        self.normals.extend_from_slice(&[normal; 4]);
+        self.colors.extend_from_slice(&[[r * brightness, g * brightness, b * brightness, a]; 4]);
+        self.uvs.extend_from_slice(&texture.rotate_uvs(face_uvs(&corners, normal)));
+        self.tiles.extend_from_slice(&[texture.tile as u32; 4]);
+        self.indices.extend_from_slice(&[v_index, v_index + 1, v_index + 2, v_index, v_index + 2, v_index + 3]);
+    }
+
+    // Copies the geometry out, leaving the builder's buffers for the next chunk
+    fn build(&self, topology: MeshTopology) -> Mesh {
+        let (primitive, indices) = match topology {
+            MeshTopology::TriangleList => (PrimitiveTopology::TriangleList, self.indices.clone()),
+            MeshTopology::TriangleStrip => (PrimitiveTopology::TriangleStrip, quad_strip(&self.indices)),
+        };
+        let mut mesh = Mesh::new(primitive);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.vertices.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.clone());
+        mesh.insert_attribute(ATTRIBUTE_ATLAS_TILE, self.tiles.clone());
+        // Half the index memory whenever every vertex fits a u16
+        let indices = if self.vertices.len() <= u16::MAX as usize + 1 {
+            Indices::U16(indices.iter().map(|&index| index as u16).collect())
+        } else {
+            Indices::U32(indices)
+        };
+        mesh.set_indices(Some(indices));
+        mesh
+    }
+}
+
+// Joins the quads `add_face` lists as two triangles, (v0, v1, v2) and
+// (v0, v2, v3), into one strip. Each quad goes in as (v1, v2, v0, v3), which
+// winds both triangles as the list did. Repeating the last index of a quad and
+// the first of the next adds four degenerate triangles between them, and keeps
+// every quad starting on an even index, so the strip's alternating winding
+// stays in step.
+fn quad_strip(indices: &[u32]) -> Vec<u32> {
+    let mut strip = Vec::with_capacity(indices.len());
+    for quad in indices.chunks_exact(6) {
+        let [v0, v1, v2, v3] = [quad[0], quad[1], quad[2], quad[5]];
+        if let Some(&last) = strip.last() {
+            strip.extend_from_slice(&[last, v1]);
+        }
+        strip.extend_from_slice(&[v1, v2, v0, v3]);
+    }
+    strip
+}
+
+// UVs follow the face's world-space extent, so a quad spanning several blocks
+// tiles the texture once per block instead of stretching it.
+fn face_uvs(corners: &[[f32; 3]; 4], normal: [f32; 3]) -> [[f32; 2]; 4] {
+    let (u_axis, v_axis) = Facing::nearest(Vec3::from_array(normal)).uv_axes();
+    let min_u = corners.iter().map(|corner| corner[u_axis]).fold(f32::INFINITY, f32::min);
+    let max_v = corners.iter().map(|corner| corner[v_axis]).fold(f32::NEG_INFINITY, f32::max);
+
+    corners.map(|corner| [corner[u_axis] - min_u, max_v - corner[v_axis]])
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::tasks::TaskPool;
+    use rand::seq::SliceRandom;
+
+    use super::*;
+
+    // Meshes `chunk` on its own at `position`, with default shading and tints
+    // and nothing loaded around it
+    fn mesh_chunk(chunk: &ChunkData, position: IVec3, style: MeshStyle, lod: ChunkLod) -> MeshScratch {
+        let mut scratch = MeshScratch::default();
+        build_chunk_meshes(
+            chunk,
+            position,
+            &WorldGen::new(0),
+            &NeighborSnapshot::default(),
+            &mut scratch,
+            FaceShading::default(),
+            &BiomeTints::default(),
+            style,
+            lod,
+        );
+        scratch
+    }
+
+    // Every triangle must wind so its geometric normal points the same way as
+    // the normals stored on its vertices; otherwise back-face culling hides the
+    // face from the side it is lit from
+    fn assert_windings_match_normals(style: MeshStyle, lod: ChunkLod) {
+        let world_gen = WorldGen::new(0);
+        let position = IVec3::new(0, world_gen.surface_height_at(0, 0).div_euclid(CHUNK_SIZE), 0);
+        let hooks = GenerationHooks::default();
+        let (_, chunk) = generate_column(&world_gen, &hooks, &SurfaceCache::default(), &[position]).remove(0);
+        let scratch = mesh_chunk(&chunk, position, style, lod);
+
+        let mut triangles = 0;
+        for builder in &scratch.builders {
+            for triangle in builder.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3::from_array(builder.vertices[triangle[i] as usize]));
+                let geometric = (b - a).cross(c - a);
+                // Surface nets can collapse a quad's corners together
+                if geometric.length_squared() < 1e-8 {
+                    continue;
+                }
+                for &index in triangle {
+                    let stored = Vec3::from_array(builder.normals[index as usize]);
+                    assert!(
+                        geometric.dot(stored) > 0.0,
+                        "{style:?} {lod:?}: triangle {a} {b} {c} faces {geometric} but its vertex normal is {stored}",
+                    );
+                }
+                triangles += 1;
+            }
+        }
+        assert!(triangles > 0, "{style:?} {lod:?}: chunk {position} produced no geometry");
+    }
+
+    // Meshes every chunk of a fixed region and reports throughput per mesher,
+    // both on one thread and split across every core. The rows are the two
+    // meshers `MeshStyle` offers; there is no naive or greedy mesher to compare
+    // them against. Run with
+    // `cargo test --release meshing_throughput -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn meshing_throughput() {
+        const RADIUS: i32 = 3;
+        const ROUNDS: u32 = 3;
+
+        let world_gen = WorldGen::new(0);
+        let hooks = GenerationHooks::default();
+        let mut world_map = WorldMap::default();
+        // One extra ring so border chunks see their neighbors' voxels
+        for x in -RADIUS - 1..=RADIUS + 1 {
+            for z in -RADIUS - 1..=RADIUS + 1 {
+                let positions: Vec<IVec3> = world_gen.chunk_y_range().map(|y| IVec3::new(x, y, z)).collect();
+                world_map.chunks.extend(generate_column(&world_gen, &hooks, &SurfaceCache::default(), &positions));
+            }
+        }
+        let positions: Vec<IVec3> = world_map
+            .chunks
+            .keys()
+            .copied()
+            .filter(|position| position.x.abs() <= RADIUS && position.z.abs() <= RADIUS)
+            .collect();
+
+        // Meshes `positions` `ROUNDS` times on the calling thread, returning the triangle count
+        let mesh_all = |positions: &[IVec3], style: MeshStyle| {
+            let mut scratch = MeshScratch::default();
+            let mut triangles = 0;
+            for _ in 0..ROUNDS {
+                for position in positions {
+                    let chunk = &world_map.chunks[position];
+                    build_chunk_meshes(
+                        chunk,
+                        *position,
+                        &world_gen,
+                        &NeighborSnapshot::capture(&world_map, *position),
+                        &mut scratch,
+                        FaceShading::default(),
+                        &BiomeTints::default(),
+                        style,
+                        ChunkLod::Full,
+                    );
+                    triangles += scratch.builders.iter().map(|builder| builder.indices.len() / 3).sum::<usize>();
+                }
+            }
+            triangles
+        };
+
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        println!("\n{} chunks x {ROUNDS} rounds, {cores} cores", positions.len());
+        println!("{:<10} {:>8} {:>12} {:>14}", "mesher", "threads", "chunks/s", "triangles/s");
+        for style in [MeshStyle::Blocky, MeshStyle::Smooth] {
+            for threads in if cores > 1 { vec![1, cores] } else { vec![1] } {
+                let start = Instant::now();
+                let triangles: usize = std::thread::scope(|scope| {
+                    let batches = positions.chunks(positions.len().div_ceil(threads));
+                    let workers: Vec<_> = batches.map(|batch| scope.spawn(move || mesh_all(batch, style))).collect();
+                    workers.into_iter().map(|worker| worker.join().unwrap()).sum()
+                });
+                let seconds = start.elapsed().as_secs_f64();
+                let chunks = (positions.len() as u32 * ROUNDS) as f64;
+                let name = format!("{style:?}");
+                println!("{name:<10} {threads:>8} {:>12.0} {:>14.0}", chunks / seconds, triangles as f64 / seconds);
+            }
+        }
+    }
+
+    // Generates a fixed region twice through one surface cache, so the second
+    // pass shows what reusing the samples saves. Run like `meshing_throughput`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn generation_throughput() {
+        const RADIUS: i32 = 6;
+
+        let world_gen = WorldGen::new(0);
+        let hooks = GenerationHooks::default();
+        let surface_cache = SurfaceCache::default();
+        let columns: Vec<Vec<IVec3>> = (-RADIUS..=RADIUS)
+            .flat_map(|x| (-RADIUS..=RADIUS).map(move |z| (x, z)))
+            .map(|(x, z)| world_gen.chunk_y_range().map(|y| IVec3::new(x, y, z)).collect())
+            .collect();
+        let chunks: usize = columns.iter().map(Vec::len).sum();
+
+        println!("\n{} columns, {chunks} chunks", columns.len());
+        println!("{:<10} {:>12}", "surface", "chunks/s");
+        for pass in ["sampled", "cached"] {
+            let start = Instant::now();
+            for positions in &columns {
+                std::hint::black_box(generate_column(&world_gen, &hooks, &surface_cache, positions));
+            }
+            println!("{pass:<10} {:>12.0}", chunks as f64 / start.elapsed().as_secs_f64());
+        }
+    }
+
+    // A feature crossing chunk borders, built the way `GenerationHooks` asks:
+    // each column's RNG decides whether a tree roots there, and every chunk
+    // the tree reaches writes its own part of it
+    fn plant_trees(position: IVec3, chunk: &mut ChunkData, world_gen: &WorldGen) {
+        const CANOPY_RADIUS: i32 = 2;
+        let origin = chunk_to_world_origin(position);
+        let mut set = |world_pos: IVec3, block: BlockType, only_air: bool| {
+            let local = world_pos - origin;
+            let inside = local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all();
+            if inside && (!only_air || chunk.get(local) == BlockType::Air) {
+                chunk.set(local, block);
+            }
+        };
+        let reach = origin.xz() - IVec2::splat(CANOPY_RADIUS)..origin.xz() + IVec2::splat(CHUNK_SIZE + CANOPY_RADIUS);
+        for x in reach.start.x..reach.end.x {
+            for z in reach.start.y..reach.end.y {
+                let mut rng = position_rng(world_gen.seed, IVec3::new(x, 0, z));
+                if rng.gen_range(0, 30) != 0 {
+                    continue;
+                }
+                let ground = world_gen.surface_height_at(x, z);
+                let top = ground + rng.gen_range(3, 6);
+                for y in ground + 1..=top {
+                    set(IVec3::new(x, y, z), BlockType::Log(Facing::Up), false);
+                }
+                let radius = -CANOPY_RADIUS..=CANOPY_RADIUS;
+                let canopy = radius.clone().flat_map(|dx| radius.clone().map(move |dz| IVec2::new(dx, dz)));
+                for offset in canopy.flat_map(|offset| (-1..=1).map(move |dy| IVec3::new(offset.x, dy, offset.y))) {
+                    if offset.length_squared() <= 6 {
+                        set(IVec3::new(x, top, z) + offset, BlockType::Leaves, true);
+                    }
+                }
+            }
+        }
+    }
+
+    // Generating a region all at once, column by column, and one chunk at a
+    // time in a shuffled order must give the same voxels, both for features
+    // crossing chunk borders and for edits waiting on unloaded chunks
+    #[test]
+    fn region_generation_is_order_independent() {
+        let world_gen = WorldGen::new(7);
+        let mut hooks = GenerationHooks::default();
+        hooks.add(plant_trees);
+        let positions: Vec<IVec3> = (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |z| (x, z)))
+            .flat_map(|(x, z)| world_gen.chunk_y_range().map(move |y| IVec3::new(x, y, z)))
+            .collect();
+        // Edits along a diagonal through every column, just above the ground
+        let edits: Vec<(IVec3, BlockType)> = (-CHUNK_SIZE..2 * CHUNK_SIZE)
+            .map(|i| (IVec3::new(i, world_gen.surface_height_at(i, i) + 2, i), BlockType::Glowstone))
+            .collect();
+
+        // Loads `chunks` in order, writing pending edits after each like the game does per frame
+        let load = |chunks: Vec<(IVec3, ChunkData)>| {
+            let mut world = World::new();
+            world.init_resource::<WorldMap>();
+            world.init_resource::<Events<BlockChanged>>();
+            let mut pending = fill::PendingEdits::default();
+            for &(world_pos, block) in &edits {
+                pending.push(world_pos, block);
+            }
+            world.insert_resource(pending);
+            for (position, chunk) in chunks {
+                world.resource_mut::<WorldMap>().chunks.insert(position, chunk);
+                world.run_system_once(fill::apply_pending_edits);
+            }
+            assert!(world.resource::<fill::PendingEdits>().is_empty());
+            world.remove_resource::<WorldMap>().unwrap()
+        };
+
+        let whole_cache = SurfaceCache::default();
+        let whole = load(
+            positions
+                .chunks(world_gen.chunk_y_range().count())
+                .flat_map(|column| generate_column(&world_gen, &hooks, &whole_cache, column))
+                .collect(),
+        );
+
+        let mut shuffled = positions.clone();
+        shuffled.shuffle(&mut position_rng(1, IVec3::ZERO));
+        let single_cache = SurfaceCache::default();
+        let one_by_one = load(
+            shuffled
+                .iter()
+                .flat_map(|&position| generate_column(&world_gen, &hooks, &single_cache, &[position]))
+                .collect(),
+        );
+
+        let voxels = whole.chunks.values().flat_map(|chunk| &chunk.voxels);
+        let trunks = voxels.filter(|block| matches!(block, BlockType::Log(_))).count();
+        assert!(trunks > 0, "the region grew no trees");
+        for position in &positions {
+            assert!(
+                whole.chunks[position].voxels == one_by_one.chunks[position].voxels,
+                "chunk {position} differs between generation orders",
+            );
+        }
+    }
+
+    // Queried columns must describe the chunks later generated there
+    #[test]
+    fn column_queries_match_generated_chunks() {
+        let world_gen = WorldGen::new(7);
+        let hooks = GenerationHooks::default();
+        let positions: Vec<IVec3> = world_gen.chunk_y_range().map(|y| IVec3::new(2, y, -3)).collect();
+        let chunks: HashMap<IVec3, ChunkData> =
+            generate_column(&world_gen, &hooks, &SurfaceCache::default(), &positions).into_iter().collect();
+        let voxel = |world: IVec3| {
+            let (chunk_pos, local) = world_to_local(world);
+            chunks[&chunk_pos].get(local)
+        };
+
+        let origin = chunk_to_world_origin(positions[0]);
+        for (x, z) in [(0, 0), (5, 11), (15, 15)] {
+            let (x, z) = (origin.x + x, origin.z + z);
+            let height = world_gen.surface_height_at(x, z);
+            assert!(voxel(IVec3::new(x, height - 1, z)).is_solid(), "column {x} {z} is lower than {height}");
+            assert!(!voxel(IVec3::new(x, height, z)).is_solid(), "column {x} {z} is higher than {height}");
+            // Shores, which depend on the biome, are topped with sand
+            let top = voxel(IVec3::new(x, height - 1, z));
+            assert_eq!(top == BlockType::Sand, world_gen.is_beach(x, z, height), "column {x} {z} is {top:?}");
+        }
+    }
+
+    // A world floor below zero from `Settings` reaches the generator, which
+    // then lays bedrock there and streams chunks down to it
+    #[test]
+    fn settings_move_the_world_floor() {
+        let mut world = streaming_world();
+        let mut settings = world.resource_mut::<Settings>();
+        (settings.world_bottom, settings.world_top) = (-24, 47);
+        world.run_system_once(apply_terrain_settings);
+
+        let world_gen = world.resource::<WorldGen>();
+        assert_eq!(world_gen.chunk_y_range(), -2..=2);
+        assert_eq!(world_gen.voxel_at(IVec3::new(0, -24, 0)), BlockType::Bedrock);
+        assert_eq!(world_gen.voxel_at(IVec3::new(0, -25, 0)), BlockType::Air);
+        assert!(world_gen.voxel_at(IVec3::new(0, -12, 0)).is_solid(), "ground fills in below the old floor");
+        assert!(!world_gen.in_bounds(48));
+    }
+
+    // The same render-space span sampled at two voxel scales, tall enough
+    // that neither surface is clamped, must trace the same hills
+    #[test]
+    fn surface_keeps_its_size_across_voxel_scales() {
+        let coarse = WorldGen { top_y: 127, voxel_scale: 1.0, ..WorldGen::new(0) };
+        let fine = WorldGen { top_y: 127, voxel_scale: 0.5, ..WorldGen::new(0) };
+        for (x, z) in (-64..64).step_by(4).flat_map(|x| (-64..64).step_by(4).map(move |z| (x, z))) {
+            let coarse_height = coarse.surface_at(x, z) * coarse.voxel_scale;
+            let fine_height = fine.surface_at(x * 2, z * 2) * fine.voxel_scale;
+            assert!((coarse_height - fine_height).abs() < 1e-9, "at ({x}, {z}): {coarse_height} vs {fine_height}");
+        }
+        // Twice the blocks for the same hill
+        assert_eq!(fine.surface_height_at(0, 0), solid_height(coarse.surface_at(0, 0) * 2.0));
+    }
+
+    // A world holding everything chunk streaming reads and writes, with
+    // saving pointed at a directory that never exists
+    fn streaming_world() -> World {
+        let mut world = World::new();
+        let mut world_save = WorldSave::default();
+        world_save.dir = std::env::temp_dir().join("voxel-world-test-no-save");
+        world.insert_resource(world_save);
+        world.insert_resource(WorldGen::new(0));
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<ChunkMaterials>();
+        world.init_resource::<Settings>();
+        world.init_resource::<ChunkIndex>();
+        world.init_resource::<WorldMap>();
+        world.init_resource::<ChunkTimings>();
+        world.init_resource::<MeshScratch>();
+        world.init_resource::<RemeshQueue>();
+        world.init_resource::<GenerationHooks>();
+        world.init_resource::<SurfaceCache>();
+        world.init_resource::<ChunkTasks>();
+        world.init_resource::<WorldOrigin>();
+        world
+    }
+
+    // `spawn_chunk` leaves draw order to Bevy: the opaque phase keys each
+    // mesh by the view depth of its translation, a chunk's low corner, and
+    // draws the nearest first. Checks that with Bevy's own rangefinder and sort.
+    #[test]
+    fn the_opaque_pass_draws_near_chunks_first() {
+        use bevy::core_pipeline::core_3d::Opaque3d;
+        use bevy::render::render_phase::{Draw, DrawFunctions, PhaseItem, TrackedRenderPass, ViewRangefinder3d};
+        use bevy::render::render_resource::CachedRenderPipelineId;
+
+        struct NoDraw;
+        impl Draw<Opaque3d> for NoDraw {
+            fn draw<'w>(&mut self, _: &'w World, _: &mut TrackedRenderPass<'w>, _: Entity, _: &Opaque3d) {}
+        }
+
+        let origin = WorldOrigin { chunk: IVec3::new(3, 0, -2), voxel_scale: 0.5 };
+        let eye = origin.to_render(IVec3::new(56, 8, -32));
+        let camera = Transform::from_translation(eye).looking_to(Vec3::NEG_Z, Vec3::Y);
+        let rangefinder = ViewRangefinder3d::from_view_matrix(&camera.compute_matrix());
+        let draw_function = DrawFunctions::<Opaque3d>::default().write().add(NoDraw);
+        // Each item's entity index is how many chunks out it lies
+        let item = |chunk: IVec3, entity: u32| Opaque3d {
+            distance: rangefinder.distance_translation(&origin.to_render(chunk_to_world_origin(chunk))),
+            pipeline: CachedRenderPipelineId::INVALID,
+            entity: Entity::from_raw(entity),
+            draw_function,
+            batch_range: 0..1,
+            dynamic_offset: None,
+        };
+
+        // Ahead of the camera along -z, one, three and six chunks out
+        let mut items: Vec<Opaque3d> = [(-8, 6), (-3, 1), (-5, 3)].map(|(z, n)| item(IVec3::new(3, 0, z), n)).into();
+        Opaque3d::sort(&mut items);
+        let order: Vec<u32> = items.iter().map(|item| item.entity().index()).collect();
+        assert_eq!(order, [1, 3, 6]);
+        assert!(items.windows(2).all(|pair| pair[0].sort_key() < pair[1].sort_key()));
+    }
+
+    // Preloading the spawn twice must not give any chunk a second entity
+    #[test]
+    fn chunks_spawn_once_per_position() {
+        let mut world = streaming_world();
+        world.run_system_once(preload_spawn_chunks);
+        world.run_system_once(preload_spawn_chunks);
+
+        let mut positions = HashSet::new();
+        for chunk in world.query::<&Chunk>().iter(&world) {
+            assert!(positions.insert(chunk.position), "two entities for chunk {}", chunk.position);
+        }
+        assert!(!positions.is_empty());
+        assert_eq!(positions.len(), world.resource::<ChunkIndex>().chunks.len());
+    }
+
+    // Racing across the world faster than columns generate leaves tasks
+    // behind every frame; none may outlive its column's range, and the freed
+    // slots go to columns around the player
+    #[test]
+    fn generation_drops_columns_the_player_left_behind() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = streaming_world();
+        let settings = world.resource::<Settings>();
+        let range = settings.render_distance + UNLOAD_MARGIN;
+        let radius = settings.render_distance;
+        world.insert_resource(ChunkStreaming { radius, budget: Duration::from_secs(1), played: 0.0 });
+        let player = world.spawn((Player, Transform::default())).id();
+
+        for step in 0..10 {
+            let column = IVec2::new(step * (range * 2 + 1), 0);
+            let eye = Vec3::new(column.x as f32, 0.0, column.y as f32) * CHUNK_SIZE as f32;
+            world.get_mut::<Transform>(player).unwrap().translation = eye;
+            world.run_system_once(generate_chunks);
+
+            let tasks = &world.resource::<ChunkTasks>().tasks;
+            assert_eq!(tasks.len(), world.resource::<Settings>().max_concurrent_chunk_tasks);
+            for task_column in tasks.keys() {
+                let distance = (*task_column - column).abs().max_element();
+                assert!(distance <= range, "step {step} still generates column {task_column}");
+            }
+        }
+    }
+
+    // Regenerating a chunk and remeshing the rest swaps meshes out from under
+    // chunk entities; afterwards every handle they hold must still resolve,
+    // and no replaced mesh may linger
+    #[test]
+    fn reloading_leaves_no_dangling_mesh_handles() {
+        let mut world = streaming_world();
+        world.run_system_once(preload_spawn_chunks);
+
+        let spawn = spawn_point(world.resource::<Settings>(), world.resource::<WorldGen>());
+        let eye = world.resource::<WorldOrigin>().to_render(spawn.floor().as_ivec3());
+        world.spawn((Player, Transform::from_translation(eye)));
+        let mut input = Input::<KeyCode>::default();
+        input.press(KeyCode::F6);
+        world.insert_resource(input);
+        world.run_system_once(regenerate_player_chunk);
+
+        world.resource_mut::<Settings>().mesh_topology = MeshTopology::TriangleStrip;
+        let positions: Vec<IVec3> = world.resource::<ChunkIndex>().chunks.keys().copied().collect();
+        world.resource_mut::<RemeshQueue>().chunks.extend(positions);
+        world.run_system_once(remesh_chunks);
+
+        let handles: Vec<Handle<Mesh>> = world.query::<&Handle<Mesh>>().iter(&world).cloned().collect();
+        let meshes = world.resource::<Assets<Mesh>>();
+        assert!(!handles.is_empty());
+        for handle in &handles {
+            assert!(meshes.contains(handle), "a chunk entity holds removed mesh {:?}", handle.id());
+        }
+        assert_eq!(meshes.len(), handles.len(), "replaced meshes were left behind");
+    }
+
+    #[test]
+    fn reset_world_leaves_nothing_loaded() {
+        let mut world = streaming_world();
+        let mut pending = fill::PendingEdits::default();
+        pending.push(IVec3::new(1000, 0, 1000), BlockType::Stone);
+        world.insert_resource(pending);
+        world.run_system_once(preload_spawn_chunks);
+        assert!(world.query::<&Chunk>().iter(&world).next().is_some());
+
+        world.run_system_once(reset_world);
+        assert_eq!(world.query::<&Chunk>().iter(&world).count(), 0);
+        assert!(world.resource::<WorldMap>().chunks.is_empty());
+        assert!(world.resource::<ChunkIndex>().chunks.is_empty());
+        assert!(world.resource::<fill::PendingEdits>().is_empty());
+        // Submesh children go with their chunk, and no mesh is leaked
+        assert_eq!(world.query::<&Handle<Mesh>>().iter(&world).count(), 0);
+        assert_eq!(world.resource::<Assets<Mesh>>().len(), 0);
+    }
+
+    // A strip must draw exactly the triangles of the list it replaces, each
+    // wound the same way, over the same vertices
+    #[test]
+    fn triangle_strips_draw_the_same_triangles_as_lists() {
+        let world_gen = WorldGen::new(0);
+        let position = IVec3::new(0, world_gen.surface_height_at(0, 0).div_euclid(CHUNK_SIZE), 0);
+        let hooks = GenerationHooks::default();
+        let (_, chunk) = generate_column(&world_gen, &hooks, &SurfaceCache::default(), &[position]).remove(0);
+        let scratch = mesh_chunk(&chunk, position, MeshStyle::Blocky, ChunkLod::Full);
+        let builder = scratch.builder(MaterialGroup::Opaque);
+        let (list, strip) = (builder.build(MeshTopology::TriangleList), builder.build(MeshTopology::TriangleStrip));
+        assert_eq!(strip.primitive_topology(), PrimitiveTopology::TriangleStrip);
+
+        // Each triangle rotated to start at its lowest index, which keeps its winding
+        let canonical = |[a, b, c]: [u32; 3]| match a.min(b).min(c) {
+            min if min == a => [a, b, c],
+            min if min == b => [b, c, a],
+            _ => [c, a, b],
+        };
+        let indices = |mesh: &Mesh| mesh.indices().unwrap().iter().map(|index| index as u32).collect::<Vec<_>>();
+        let mut listed: Vec<[u32; 3]> =
+            indices(&list).chunks_exact(3).map(|triangle| canonical([triangle[0], triangle[1], triangle[2]])).collect();
+        // Odd triangles of a strip are wound the other way round
+        let strip_indices = indices(&strip);
+        let mut stripped: Vec<[u32; 3]> = strip_indices
+            .windows(3)
+            .enumerate()
+            .map(|(i, w)| if i % 2 == 0 { [w[0], w[1], w[2]] } else { [w[1], w[0], w[2]] })
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+            .map(canonical)
+            .collect();
+        listed.sort();
+        stripped.sort();
+        assert!(!listed.is_empty());
+        assert_eq!(listed, stripped);
+
+        // Both draw from one set of vertices, so every attribute still lines up
+        let attributes = [Mesh::ATTRIBUTE_POSITION, Mesh::ATTRIBUTE_NORMAL, Mesh::ATTRIBUTE_COLOR];
+        for attribute in attributes.into_iter().chain([Mesh::ATTRIBUTE_UV_0, ATTRIBUTE_ATLAS_TILE]) {
+            assert_eq!(
+                list.attribute(attribute.id).unwrap().get_bytes(),
+                strip.attribute(attribute.id).unwrap().get_bytes(),
+                "{} differs",
+                attribute.name,
+            );
+        }
+    }
+
+    #[test]
+    fn small_meshes_use_u16_indices() {
+        // High in the sky, so nothing beyond the chunk hides its faces
+        let build = |chunk: &ChunkData| {
+            mesh_chunk(chunk, IVec3::new(0, 10, 0), MeshStyle::Blocky, ChunkLod::Full)
+                .builder(MaterialGroup::Opaque)
+                .build(MeshTopology::TriangleList)
+        };
+
+        let mut sparse = ChunkData::new();
+        sparse.set(IVec3::splat(8), BlockType::Stone);
+        assert!(matches!(build(&sparse).indices(), Some(Indices::U16(indices)) if !indices.is_empty()));
+
+        // A checkerboard shows every face of half the voxels, the most a culled
+        // chunk can, and still fits
+        let mut checkerboard = ChunkData::new();
+        for local in ChunkData::iter_coords().filter(|local| (local.x + local.y + local.z) % 2 == 0) {
+            checkerboard.set(local, BlockType::Stone);
+        }
+        let mesh = build(&checkerboard);
+        assert_eq!(mesh.count_vertices(), (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE / 2 * 6 * 4) as usize);
+        assert!(matches!(mesh.indices(), Some(Indices::U16(_))));
+
+        // Every face of every voxel is more vertices than a u16 can address
+        let mut builder = ChunkMeshBuilder::default();
+        for IVec3 { x, y, z } in ChunkData::iter_coords() {
+            builder.add_cube(x, y, z, 1, 1.0, BlockType::Stone, Color::GRAY, Color::GRAY, [1.0; 6], [true; 6]);
+        }
+        let mesh = builder.build(MeshTopology::TriangleList);
+        assert!(mesh.count_vertices() > u16::MAX as usize);
+        assert!(matches!(mesh.indices(), Some(Indices::U32(_))));
+    }
+
+    #[test]
+    fn blocky_windings_match_normals() {
+        assert_windings_match_normals(MeshStyle::Blocky, ChunkLod::Full);
+        assert_windings_match_normals(MeshStyle::Blocky, ChunkLod::Half);
+    }
+
+    #[test]
+    fn smooth_windings_match_normals() {
+        assert_windings_match_normals(MeshStyle::Smooth, ChunkLod::Full);
+        assert_windings_match_normals(MeshStyle::Smooth, ChunkLod::Half);
+    }
+
+    // A log lying along x shows its rings on the x faces, and the bark's
+    // grain, which runs along v, follows x on the four others
+    #[test]
+    fn log_faces_follow_its_axis() {
+        let mut chunk = ChunkData::new();
+        chunk.set(IVec3::ZERO, BlockType::Log(Facing::PosX));
+        let scratch = mesh_chunk(&chunk, IVec3::new(0, 8, 0), MeshStyle::Blocky, ChunkLod::Full);
+
+        let builder = scratch.builder(MaterialGroup::Opaque);
+        assert_eq!(builder.tiles.len(), 6 * 4);
+        for face in 0..6 {
+            let vertices = face * 4..face * 4 + 4;
+            let normal = builder.normals[face * 4];
+            let tile = builder.tiles[face * 4];
+            if normal[0] != 0.0 {
+                assert_eq!(tile, AtlasTile::LogEnd as u32, "face {normal:?}");
+                continue;
+            }
+            assert_eq!(tile, AtlasTile::LogBark as u32, "face {normal:?}");
+            // Wherever v changes between two corners, x must change with it
+            for (a, b) in vertices.clone().zip(vertices.clone().cycle().skip(1)) {
+                let (from, to) = (Vec3::from_array(builder.vertices[a]), Vec3::from_array(builder.vertices[b]));
+                if builder.uvs[a][1] != builder.uvs[b][1] {
+                    assert_eq!((to - from).abs(), Vec3::X, "face {normal:?} runs its grain across {from} {to}");
+                }
+            }
+        }
+    }
+
+    // Every voxel from three chunks below zero to three above, on each axis
+    // in turn and on all three at once, lands in exactly one chunk and back
+    #[test]
+    fn voxel_chunk_conversions_round_trip_around_zero() {
+        for value in -3 * CHUNK_SIZE..3 * CHUNK_SIZE {
+            let positions =
+                [IVec3::new(value, 0, 0), IVec3::new(0, value, 0), IVec3::new(0, 0, value), IVec3::splat(value)];
+            for world_pos in positions {
+                let (chunk_pos, local) = world_to_local(world_pos);
+                assert_eq!(chunk_pos, world_to_chunk(world_pos));
+                assert!(local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all(), "{world_pos}");
+                assert_eq!(chunk_to_world_origin(chunk_pos) + local, world_pos);
+            }
+        }
+    }
+
+    #[test]
+    fn negative_voxels_belong_to_negative_chunks() {
+        let cases = [
+            (0, 0, 0),
+            (15, 0, 15),
+            (16, 1, 0),
+            (-1, -1, 15),
+            (-15, -1, 1),
+            (-16, -1, 0),
+            (-17, -2, 15),
+            (-32, -2, 0),
+        ];
+        for (world, chunk, local) in cases {
+            let expected = (IVec3::splat(chunk), IVec3::splat(local));
+            assert_eq!(world_to_local(IVec3::splat(world)), expected, "voxel {world}");
+        }
+        assert_eq!(chunk_to_world_origin(IVec3::new(-1, 0, 2)), IVec3::new(-16, 0, 32));
+
+        // Render positions just below the origin are in the chunk below it, not chunk zero
+        let origin = WorldOrigin { chunk: IVec3::new(3, 0, -2), voxel_scale: 0.5 };
+        assert_eq!(origin.chunk_at(Vec3::new(-0.1, 0.0, 0.1)), IVec3::new(2, 0, -2));
+        assert_eq!(origin.chunk_at(Vec3::new(0.0, -0.1, -8.1)), IVec3::new(3, -1, -4));
+    }
+
+    #[test]
+    fn render_distance_grows_once_play_starts() {
+        let settings = Settings {
+            render_distance: 12,
+            initial_render_distance: 2,
+            render_distance_ramp: 4.0,
+            ..default()
+        };
+        assert_eq!(settings.ramped_render_distance(0.0), 2);
+        assert_eq!(settings.ramped_render_distance(2.0), 7);
+        assert_eq!(settings.ramped_render_distance(4.0), 12);
+        assert_eq!(settings.ramped_render_distance(60.0), 12);
+
+        // Without a ramp the full radius loads as soon as play starts
+        let instant = Settings { render_distance_ramp: 0.0, ..settings };
+        assert_eq!(instant.ramped_render_distance(0.0), 2);
+        assert_eq!(instant.ramped_render_distance(0.01), 12);
+
+        // An initial radius past the full one is only the full one
+        let small = Settings { render_distance: 1, initial_render_distance: 3, ..default() };
+        assert_eq!(small.ramped_render_distance(0.0), 1);
+    }
+
+    // Faces `group` draws on the plane between a voxel of `low` and one of
+    // `high` just past it along x, on their own in the sky
+    fn faces_between(low: BlockType, high: BlockType, group: MaterialGroup) -> usize {
+        let mut chunk = ChunkData::new();
+        chunk.set(IVec3::splat(5), low);
+        chunk.set(IVec3::new(6, 5, 5), high);
+        let scratch = mesh_chunk(&chunk, IVec3::new(0, 8, 0), MeshStyle::Blocky, ChunkLod::Full);
+        let builder = scratch.builder(group);
+        builder.vertices.chunks_exact(4).filter(|face| face.iter().all(|corner| corner[0] == 6.0)).count()
+    }
+
+    #[test]
+    fn water_draws_no_face_against_water() {
+        assert_eq!(faces_between(BlockType::Water, BlockType::Water, MaterialGroup::Water), 0);
+        assert_eq!(faces_between(BlockType::Water, BlockType::FlowingWater(3), MaterialGroup::Water), 0);
+    }
+
+    #[test]
+    fn water_draws_its_face_against_air() {
+        assert_eq!(faces_between(BlockType::Water, BlockType::Air, MaterialGroup::Water), 1);
+        assert_eq!(faces_between(BlockType::Air, BlockType::Water, MaterialGroup::Water), 1);
+    }
+
+    // Both faces show, each seen through the other block
+    #[test]
+    fn water_and_glass_both_draw_their_faces() {
+        assert_eq!(faces_between(BlockType::Water, BlockType::Glass, MaterialGroup::Water), 1);
+        assert_eq!(faces_between(BlockType::Water, BlockType::Glass, MaterialGroup::Glass), 1);
+        assert_eq!(faces_between(BlockType::Glass, BlockType::Glass, MaterialGroup::Glass), 0);
+    }
+
+    // Opaque blocks hide each other's faces, but show theirs through glass
+    #[test]
+    fn opaque_blocks_draw_no_face_against_each_other() {
+        assert_eq!(faces_between(BlockType::Stone, BlockType::Stone, MaterialGroup::Opaque), 0);
+        assert_eq!(faces_between(BlockType::Grass, BlockType::Dirt, MaterialGroup::Opaque), 0);
+        assert_eq!(faces_between(BlockType::Stone, BlockType::Glass, MaterialGroup::Opaque), 1);
+        assert_eq!(faces_between(BlockType::Stone, BlockType::Glass, MaterialGroup::Glass), 0);
+    }
+
+    // A slab only covers the bottom of its side, so the stone beside it keeps
+    // its face, while the slab's own face shows nothing the stone doesn't hide
+    #[test]
+    fn slabs_hide_no_face_of_their_neighbors() {
+        assert_eq!(faces_between(BlockType::Stone, BlockType::StoneSlab, MaterialGroup::Opaque), 1);
+        assert_eq!(faces_between(BlockType::StoneSlab, BlockType::Stone, MaterialGroup::Opaque), 1);
+    }
+
+    #[test]
+    fn slabs_are_drawn_half_a_block_tall() {
+        let mut chunk = ChunkData::new();
+        chunk.set(IVec3::splat(5), BlockType::StoneSlab);
+        chunk.set(IVec3::new(5, 6, 5), BlockType::Stone);
+        for style in [MeshStyle::Blocky, MeshStyle::Smooth] {
+            let scratch = mesh_chunk(&chunk, IVec3::new(0, 8, 0), style, ChunkLod::Full);
+            let slab_faces: Vec<_> = scratch
+                .builder(MaterialGroup::Opaque)
+                .vertices
+                .chunks_exact(4)
+                .filter(|face| face.iter().all(|corner| corner[1] <= 5.5))
+                .map(|face| face.iter().fold(0.0, |top: f32, corner| top.max(corner[1])))
+                .collect();
+            // Four sides, the bottom, and the top left open under the stone
+            assert_eq!(slab_faces.len(), 6, "{style:?}");
+            assert!(slab_faces.iter().all(|top| *top == 5.5 || *top == 5.0), "{style:?}");
+        }
+    }
+
+    // Digs out 100 voxels of one chunk in a single frame, away from its faces
+    // so no neighbor needs rebuilding, and expects a single remesh
+    #[test]
+    fn edits_in_one_frame_remesh_once() {
+        let mut world = World::new();
+        let world_gen = WorldGen::new(0);
+        let position = IVec3::new(0, world_gen.surface_height_at(0, 0).div_euclid(CHUNK_SIZE), 0);
+        let hooks = GenerationHooks::default();
+        let (_, chunk) = generate_column(&world_gen, &hooks, &SurfaceCache::default(), &[position]).remove(0);
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<ChunkMaterials>();
+        world.init_resource::<Settings>();
+        world.init_resource::<RemeshQueue>();
+        world.init_resource::<ChunkTimings>();
+        world.init_resource::<MeshScratch>();
+        world.init_resource::<Events<BlockChanged>>();
+        world.insert_resource(world_gen);
+        world.insert_resource(WorldMap {
+            chunks: HashMap::from_iter([(position, chunk)]),
+        });
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(Mesh::new(PrimitiveTopology::TriangleList));
+        let entity = world.spawn(mesh).id();
+        let entry = ChunkEntry {
+            entity,
+            submeshes: HashMap::new(),
+            empty: false,
+            modified: false,
+            lod: ChunkLod::Full,
+            out_of_range_since: None,
+        };
+        world.insert_resource(ChunkIndex {
+            chunks: HashMap::from_iter([(position, entry)]),
+        });
+
+        let dig = move |mut world_map: ResMut<WorldMap>, mut events: EventWriter<BlockChanged>| {
+            for (x, z) in (2..12).flat_map(|x| (2..12).map(move |z| (x, z))) {
+                let world_pos = chunk_to_world_origin(position) + IVec3::new(x, 7, z);
+                edit_block(&mut world_map, &mut events, world_pos, BlockType::Air);
+            }
+        };
+        let mut frame = Schedule::default();
+        frame.add_systems((dig, queue_block_changes, relight_block_changes, remesh_chunks).chain());
+        frame.run(&mut world);
+
+        let edits = world.resource::<Events<BlockChanged>>().len();
+        assert_eq!(edits, 100);
+        assert_eq!(world.resource::<ChunkTimings>().remeshes, 1);
+        assert!(world.resource::<ChunkIndex>().chunks[&position].modified);
+    }
+}
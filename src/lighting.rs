@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::{
+    chunk_to_world_origin, world_to_chunk, BlockChanged, BlockType, ChunkData, RemeshQueue, WorldGen, WorldMap,
+    CHUNK_SIZE,
+};
+
+pub const MAX_LIGHT: u8 = 15;
+// Brightness kept per light level, so fully dark faces end up almost black
+const LIGHT_FALLOFF: f32 = 0.8;
+const NEIGHBORS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+// Sunlight pours in from above the world; block light comes from emissive blocks
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LightChannel {
+    Sky,
+    Block,
+}
+
+impl LightChannel {
+    const ALL: [LightChannel; 2] = [LightChannel::Sky, LightChannel::Block];
+}
+
+// Vertex color multiplier for a light level
+pub fn light_brightness(level: u8) -> f32 {
+    LIGHT_FALLOFF.powi(i32::from(MAX_LIGHT - level.min(MAX_LIGHT)))
+}
+
+// Levels absorbed on top of the one lost per step, or `None` if light can't enter
+fn absorption(block: BlockType) -> Option<u8> {
+    // Light filters through the gaps in foliage, so leaf faces inside a canopy aren't black
+    if block == BlockType::Leaves {
+        Some(1)
+    } else if block == BlockType::Glass {
+        Some(0)
+    } else if block.is_solid() {
+        None
+    } else if block.is_water() {
+        Some(2)
+    } else {
+        Some(0)
+    }
+}
+
+// Full sunlight falls straight down without fading
+fn falls_unfaded(channel: LightChannel, level: u8, dir: IVec3) -> bool {
+    channel == LightChannel::Sky && dir == IVec3::NEG_Y && level == MAX_LIGHT
+}
+
+// Light reaching a `to` block from a neighbor at `level`, travelling along `dir`
+fn spread(channel: LightChannel, level: u8, dir: IVec3, to: BlockType) -> u8 {
+    let Some(absorbed) = absorption(to) else {
+        return 0;
+    };
+    let level = if falls_unfaded(channel, level, dir) { level } else { level.saturating_sub(1) };
+    level.saturating_sub(absorbed)
+}
+
+// Everything above the world is open sky
+fn light_at(world_map: &WorldMap, world_gen: &WorldGen, world_pos: IVec3, channel: LightChannel) -> Option<u8> {
+    if channel == LightChannel::Sky && world_pos.y > world_gen.top_y {
+        Some(MAX_LIGHT)
+    } else {
+        world_map.light(world_pos, channel)
+    }
+}
+
+// Lights an emissive voxel with its own glow, returning whether it emits
+fn seed_emission(world_map: &mut WorldMap, world_pos: IVec3) -> bool {
+    let emission = world_map.voxel(world_pos).map_or(0, BlockType::emission);
+    if emission > 0 && world_map.light(world_pos, LightChannel::Block) < Some(emission) {
+        world_map.set_light(world_pos, LightChannel::Block, emission);
+    }
+    emission > 0
+}
+
+// Spreads light outwards from `queue`, only ever brightening voxels.
+// Records every chunk whose light changed in `touched`.
+fn flood(
+    world_map: &mut WorldMap,
+    world_gen: &WorldGen,
+    channel: LightChannel,
+    mut queue: VecDeque<IVec3>,
+    touched: &mut HashSet<IVec3>,
+) {
+    while let Some(world_pos) = queue.pop_front() {
+        let Some(level) = light_at(world_map, world_gen, world_pos, channel) else {
+            continue;
+        };
+        if level <= 1 {
+            continue;
+        }
+        for dir in NEIGHBORS {
+            let next = world_pos + dir;
+            let (Some(block), Some(current)) = (world_map.voxel(next), world_map.light(next, channel)) else {
+                continue;
+            };
+            let lit = spread(channel, level, dir, block);
+            if lit > current {
+                world_map.set_light(next, channel, lit);
+                touched.insert(world_to_chunk(next));
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+// Lights a freshly inserted chunk from its loaded neighbors, the sky and its
+// own emissive blocks, and lets its light flow back out. Returns the other
+// chunks that got brighter.
+pub fn light_new_chunk(world_map: &mut WorldMap, world_gen: &WorldGen, position: IVec3) -> HashSet<IVec3> {
+    let origin = chunk_to_world_origin(position);
+    let mut border = VecDeque::new();
+    for a in 0..CHUNK_SIZE {
+        for b in 0..CHUNK_SIZE {
+            border.extend([
+                origin + IVec3::new(-1, a, b),
+                origin + IVec3::new(CHUNK_SIZE, a, b),
+                origin + IVec3::new(a, -1, b),
+                origin + IVec3::new(a, CHUNK_SIZE, b),
+                origin + IVec3::new(a, b, -1),
+                origin + IVec3::new(a, b, CHUNK_SIZE),
+            ]);
+        }
+    }
+
+    let mut touched = HashSet::new();
+    flood(world_map, world_gen, LightChannel::Sky, border.clone(), &mut touched);
+
+    let mut seeds = border;
+    seeds.extend(ChunkData::iter_coords().map(|local| origin + local).filter(|&world_pos| seed_emission(world_map, world_pos)));
+    flood(world_map, world_gen, LightChannel::Block, seeds, &mut touched);
+
+    touched.remove(&position);
+    touched
+}
+
+// Re-propagates light around an edited voxel: first darkens everything that
+// may have been lit through it, then lets the surrounding light flow back.
+// Returns every chunk whose light changed.
+pub fn relight(world_map: &mut WorldMap, world_gen: &WorldGen, world_pos: IVec3) -> HashSet<IVec3> {
+    let mut touched = HashSet::new();
+    for channel in LightChannel::ALL {
+        relight_channel(world_map, world_gen, channel, world_pos, &mut touched);
+    }
+    touched
+}
+
+fn relight_channel(
+    world_map: &mut WorldMap,
+    world_gen: &WorldGen,
+    channel: LightChannel,
+    world_pos: IVec3,
+    touched: &mut HashSet<IVec3>,
+) {
+    let mut refill = VecDeque::new();
+    let mut removal = VecDeque::new();
+
+    if let Some(old) = world_map.light(world_pos, channel).filter(|&level| level > 0) {
+        world_map.set_light(world_pos, channel, 0);
+        touched.insert(world_to_chunk(world_pos));
+        removal.push_back((world_pos, old));
+    }
+
+    while let Some((position, level)) = removal.pop_front() {
+        for dir in NEIGHBORS {
+            let next = position + dir;
+            let Some(current) = world_map.light(next, channel).filter(|&level| level > 0) else {
+                continue;
+            };
+            if current < level || falls_unfaded(channel, level, dir) {
+                world_map.set_light(next, channel, 0);
+                touched.insert(world_to_chunk(next));
+                removal.push_back((next, current));
+                // Other light sources caught in the dark region shine again
+                if channel == LightChannel::Block && seed_emission(world_map, next) {
+                    refill.push_back(next);
+                }
+            } else {
+                refill.push_back(next);
+            }
+        }
+    }
+
+    if channel == LightChannel::Block && seed_emission(world_map, world_pos) {
+        touched.insert(world_to_chunk(world_pos));
+        refill.push_back(world_pos);
+    }
+    refill.extend(NEIGHBORS.map(|dir| world_pos + dir));
+    flood(world_map, world_gen, channel, refill, touched);
+}
+
+pub fn relight_block_changes(
+    mut events: EventReader<BlockChanged>,
+    mut world_map: ResMut<WorldMap>,
+    world_gen: Res<WorldGen>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+) {
+    for event in events.read() {
+        let touched = relight(&mut world_map, &world_gen, event.world_pos);
+        remesh_queue.chunks.extend(touched);
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{ChunkIndex, GameState, Player, WorldGen, WorldOrigin};
+
+// Chunk columns on each side of the player that make up the initial world
+const LOADING_RADIUS: i32 = 3;
+// Share of those chunks that must be in before the player gets control
+const LOADED_FRACTION: f32 = 0.9;
+#[cfg(not(feature = "headless"))]
+const BAR_WIDTH: f32 = 320.0;
+#[cfg(not(feature = "headless"))]
+const BAR_HEIGHT: f32 = 12.0;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingProgress>().add_systems(Update, track_loading);
+
+        #[cfg(not(feature = "headless"))]
+        app.add_systems(OnEnter(GameState::Loading), spawn_loading_screen)
+            .add_systems(OnExit(GameState::Loading), despawn_loading_screen)
+            .add_systems(Update, update_loading_screen.run_if(in_state(GameState::Loading)));
+    }
+}
+
+// Meshed chunks around the player, out of all those the loading screen waits on
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl LoadingProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+}
+
+// Starts play once enough of the world around the player is meshed, and
+// sends the player back to the loading screen if they end up somewhere
+// unloaded, e.g. after a teleport
+#[allow(clippy::too_many_arguments)]
+fn track_loading(
+    settings: Res<Settings>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    chunk_index: Res<ChunkIndex>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut progress: ResMut<LoadingProgress>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_chunk = world_origin.chunk_at(player_transform.translation);
+    let radius = LOADING_RADIUS.min(settings.initial_render_distance());
+    let is_loaded = |x: i32, y: i32, z: i32| {
+        chunk_index.chunks.contains_key(&IVec3::new(player_chunk.x + x, y, player_chunk.z + z))
+    };
+
+    match state.get() {
+        GameState::Loading => {
+            let mut loaded = 0;
+            let mut total = 0;
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    for y in world_gen.chunk_y_range() {
+                        total += 1;
+                        loaded += is_loaded(x, y, z) as usize;
+                    }
+                }
+            }
+            *progress = LoadingProgress { loaded, total };
+            if progress.fraction() >= LOADED_FRACTION {
+                next_state.set(GameState::Playing);
+            }
+        }
+        GameState::Playing => {
+            if !world_gen.chunk_y_range().all(|y| is_loaded(0, y, 0)) {
+                next_state.set(GameState::Loading);
+            }
+        }
+        GameState::MainMenu | GameState::Paused => {}
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+#[derive(Component)]
+struct LoadingScreen;
+
+#[cfg(not(feature = "headless"))]
+#[derive(Component)]
+struct LoadingBar;
+
+#[cfg(not(feature = "headless"))]
+#[derive(Component)]
+struct LoadingText;
+
+#[cfg(not(feature = "headless"))]
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgb(0.08, 0.08, 0.1).into(),
+                // Above the HUD and underwater overlay
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            LoadingScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                TextBundle::from_section("Loading world", TextStyle { font_size: 24.0, ..default() }),
+                LoadingText,
+            ));
+            root.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    ..default()
+                },
+                background_color: Color::rgb(0.25, 0.25, 0.3).into(),
+                ..default()
+            })
+            .with_children(|track| {
+                track.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.3, 0.7, 0.3).into(),
+                        ..default()
+                    },
+                    LoadingBar,
+                ));
+            });
+        });
+}
+
+#[cfg(not(feature = "headless"))]
+fn despawn_loading_screen(mut commands: Commands, screen_query: Query<Entity, With<LoadingScreen>>) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    mut bar_query: Query<&mut Style, With<LoadingBar>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+) {
+    // The bar fills up to the point where play starts
+    let shown = (progress.fraction() / LOADED_FRACTION).min(1.0);
+    for mut style in bar_query.iter_mut() {
+        style.width = Val::Percent(shown * 100.0);
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Loading world ({} / {} chunks)", progress.loaded, progress.total);
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+
+use crate::settings::Settings;
+use crate::{BlockType, Chunk, ChunkData, ChunkIndex, MeshStyle, Player, RemeshQueue, WorldOrigin};
+
+// Detail a chunk is meshed at, picked from its distance to the player
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ChunkLod {
+    #[default]
+    Full,
+    // One cell per 2×2×2 voxels
+    Half,
+}
+
+impl ChunkLod {
+    // `distance` is in chunks, measured like the render distance
+    pub fn for_distance(settings: &Settings, distance: i32) -> Self {
+        if distance > settings.lod_distance {
+            ChunkLod::Half
+        } else {
+            ChunkLod::Full
+        }
+    }
+
+    // Voxels along each side of a meshed cell
+    pub fn step(self) -> i32 {
+        match self {
+            ChunkLod::Full => 1,
+            ChunkLod::Half => 2,
+        }
+    }
+}
+
+// The block standing in for a `step`³ cell of voxels starting at `base`:
+// air unless at least half the cell is filled, otherwise its most common
+// block. Grass wins over dirt so coarse hills keep their color.
+pub fn coarse_block(chunk: &ChunkData, base: IVec3, step: i32) -> BlockType {
+    if step == 1 {
+        return chunk.get(base);
+    }
+    let mut counts: Vec<(BlockType, usize)> = Vec::new();
+    let mut filled = 0;
+    for z in 0..step {
+        for y in 0..step {
+            for x in 0..step {
+                let block = chunk.get(base + IVec3::new(x, y, z));
+                if block == BlockType::Air {
+                    continue;
+                }
+                filled += 1;
+                match counts.iter_mut().find(|(counted, _)| *counted == block) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((block, 1)),
+                }
+            }
+        }
+    }
+    if filled * 2 < step.pow(3) {
+        return BlockType::Air;
+    }
+
+    // Ties go to the block found first
+    let mut common = counts[0];
+    for &(block, count) in &counts[1..] {
+        if count > common.1 {
+            common = (block, count);
+        }
+    }
+    let has_grass = counts.iter().any(|&(block, _)| block == BlockType::Grass);
+    if common.0 == BlockType::Dirt && has_grass {
+        BlockType::Grass
+    } else {
+        common.0
+    }
+}
+
+// A blocky chunk easing between its full-detail mesh and the shape that mesh
+// takes with every vertex snapped to the half-detail cell grid, which is
+// close to the coarse mesh. Only the full mesh is ever morphed: refining
+// swaps it in snapped and relaxes it, coarsening collapses it and then swaps
+// in the coarse mesh, so neither switch shows a pop.
+#[derive(Component)]
+pub struct LodMorph {
+    // The level being morphed towards
+    toward: ChunkLod,
+    elapsed: f32,
+    // Full-detail and snapped position of each opaque mesh vertex, read from
+    // it on the first frame and again whenever the chunk is remeshed mid-morph
+    positions: Option<Vec<(Vec3, Vec3)>>,
+}
+
+impl LodMorph {
+    fn new(toward: ChunkLod) -> Self {
+        Self { toward, elapsed: 0.0, positions: None }
+    }
+
+    // A mesh rebuilt at full detail keeps morphing from its new vertices,
+    // while one rebuilt coarse has already arrived. Returns whether to keep it.
+    pub fn remeshed(&mut self, style: MeshStyle) -> bool {
+        self.positions = None;
+        self.toward == ChunkLod::Full && style == MeshStyle::Blocky
+    }
+}
+
+// A vertex moved to the nearest corner of the `step`-sized cell grid
+fn snap_to_cells(position: Vec3, step: i32) -> Vec3 {
+    (position / step as f32).round() * step as f32
+}
+
+// Remeshes chunks whose detail no longer matches their distance, whenever
+// the player crosses into another chunk or the LOD distance changes. Blocky
+// chunks morph across the switch when `Settings::lod_morph_duration` allows.
+#[allow(clippy::too_many_arguments)]
+pub fn update_chunk_lod(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    world_origin: Res<WorldOrigin>,
+    mut chunk_index: ResMut<ChunkIndex>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    player_query: Query<&Transform, With<Player>>,
+    mut morph_query: Query<&mut LodMorph>,
+    mut last: Local<Option<(IVec3, i32)>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_chunk = world_origin.chunk_at(player_transform.translation);
+    if *last == Some((player_chunk, settings.lod_distance)) {
+        return;
+    }
+    *last = Some((player_chunk, settings.lod_distance));
+
+    for (&position, entry) in chunk_index.chunks.iter_mut() {
+        let lod = ChunkLod::for_distance(&settings, (position - player_chunk).xz().abs().max_element());
+        if entry.lod == lod {
+            continue;
+        }
+        entry.lod = lod;
+        // Turning back mid-morph heads back the other way from where it got to
+        if let Ok(mut morph) = morph_query.get_mut(entry.entity) {
+            morph.toward = lod;
+            morph.elapsed = (settings.lod_morph_duration - morph.elapsed).max(0.0);
+            continue;
+        }
+        let morphs = settings.lod_morph_duration > 0.0 && settings.mesh_style == MeshStyle::Blocky && !entry.empty;
+        if morphs {
+            commands.entity(entry.entity).insert(LodMorph::new(lod));
+        }
+        // A coarsening chunk keeps its full mesh until the morph ends
+        if !morphs || lod == ChunkLod::Full {
+            remesh_queue.chunks.insert(position);
+        }
+    }
+}
+
+// Moves morphing chunks' vertices along, after any remesh this frame, and
+// swaps in the coarse mesh once a chunk has collapsed onto it
+pub fn animate_lod_morphs(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut remesh_queue: ResMut<RemeshQueue>,
+    mut morph_query: Query<(Entity, &Chunk, &mut LodMorph, &Handle<Mesh>)>,
+) {
+    for (entity, chunk, mut morph, handle) in morph_query.iter_mut() {
+        let Some(mesh) = meshes.get_mut(handle) else {
+            continue;
+        };
+        let Some(VertexAttributeValues::Float32x3(vertices)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) else {
+            continue;
+        };
+        let morph = &mut *morph;
+        morph.elapsed += time.delta_seconds();
+        let progress = (morph.elapsed / settings.lod_morph_duration.max(f32::EPSILON)).min(1.0);
+        let detail = if morph.toward == ChunkLod::Full { progress } else { 1.0 - progress };
+
+        let step = ChunkLod::Half.step();
+        let positions = morph.positions.get_or_insert_with(|| {
+            let full = vertices.iter().map(|&vertex| Vec3::from(vertex));
+            full.map(|full| (full, snap_to_cells(full, step))).collect()
+        });
+        for (vertex, &(full, snapped)) in vertices.iter_mut().zip(positions.iter()) {
+            *vertex = snapped.lerp(full, detail).to_array();
+        }
+
+        if progress >= 1.0 {
+            commands.entity(entity).remove::<LodMorph>();
+            if morph.toward != ChunkLod::Full {
+                remesh_queue.chunks.insert(chunk.position);
+            }
+        }
+    }
+}
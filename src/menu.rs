@@ -0,0 +1,457 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::settings::Settings;
+use crate::{reset_world, GameState, WorldGen};
+
+const PANEL_COLOR: Color = Color::rgba(0.08, 0.08, 0.1, 0.9);
+const BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.25);
+const BUTTON_HOVER_COLOR: Color = Color::rgb(0.3, 0.3, 0.38);
+const BUTTON_PRESSED_COLOR: Color = Color::rgb(0.35, 0.6, 0.35);
+const TRACK_COLOR: Color = Color::rgb(0.25, 0.25, 0.3);
+const FILL_COLOR: Color = Color::rgb(0.3, 0.7, 0.3);
+const TRACK_WIDTH: f32 = 240.0;
+const BACKDROP_COLOR: Color = Color::rgb(0.08, 0.08, 0.1);
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+            .add_systems(OnExit(GameState::Paused), despawn_pause_menu)
+            .add_systems(
+                Update,
+                (press_menu_buttons, drag_sliders, update_sliders).chain().run_if(in_state(GameState::Paused)),
+            );
+    }
+}
+
+// A start screen for typing or rolling a seed before the world generates.
+// Add it next to `VoxelWorldPlugin` to open on it rather than loading straight in.
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(State::new(GameState::MainMenu))
+            .init_resource::<SeedEntry>()
+            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn_main_menu)
+            .add_systems(
+                Update,
+                (type_seed, press_main_menu_buttons, update_seed_text).chain().run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct MainMenu;
+
+#[derive(Component, Clone, Copy)]
+enum MainMenuButton {
+    Generate,
+    Random,
+}
+
+// Digits typed into the seed field; generating with it empty keeps the current seed
+#[derive(Resource, Default)]
+struct SeedEntry(String);
+
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Resume,
+    Quit,
+}
+
+// A setting the menu can adjust, with the range its slider covers
+#[derive(Component, Clone, Copy)]
+enum SliderSetting {
+    RenderDistance,
+    Sensitivity,
+    Fov,
+    Msaa,
+}
+
+// Sample counts the anti-aliasing slider steps through
+const MSAA_STEPS: [u32; 4] = [1, 2, 4, 8];
+
+impl SliderSetting {
+    const ALL: [SliderSetting; 4] = [
+        SliderSetting::RenderDistance,
+        SliderSetting::Sensitivity,
+        SliderSetting::Fov,
+        SliderSetting::Msaa,
+    ];
+
+    fn range(self) -> (f32, f32) {
+        match self {
+            SliderSetting::RenderDistance => (2.0, 16.0),
+            SliderSetting::Sensitivity => (0.0005, 0.01),
+            SliderSetting::Fov => (50.0, 110.0),
+            SliderSetting::Msaa => (0.0, (MSAA_STEPS.len() - 1) as f32),
+        }
+    }
+
+    fn get(self, settings: &Settings) -> f32 {
+        match self {
+            SliderSetting::RenderDistance => settings.render_distance as f32,
+            SliderSetting::Sensitivity => settings.mouse_sensitivity,
+            SliderSetting::Fov => settings.fov,
+            SliderSetting::Msaa => {
+                let samples = settings.msaa().samples();
+                MSAA_STEPS.iter().position(|&step| step == samples).unwrap_or_default() as f32
+            }
+        }
+    }
+
+    fn set(self, settings: &mut Settings, value: f32) {
+        match self {
+            SliderSetting::RenderDistance => settings.render_distance = value.round() as i32,
+            SliderSetting::Sensitivity => settings.mouse_sensitivity = value,
+            SliderSetting::Fov => settings.fov = value.round(),
+            SliderSetting::Msaa => settings.msaa_samples = MSAA_STEPS[value.round() as usize],
+        }
+    }
+
+    fn label(self, settings: &Settings) -> String {
+        match self {
+            SliderSetting::RenderDistance => format!("Render distance: {} chunks", settings.render_distance),
+            SliderSetting::Sensitivity => format!("Mouse sensitivity: {:.4}", settings.mouse_sensitivity),
+            SliderSetting::Fov => format!("Field of view: {:.0}°", settings.fov),
+            SliderSetting::Msaa => match settings.msaa() {
+                Msaa::Off => "Anti-aliasing: off".to_string(),
+                msaa => format!("Anti-aliasing: {}x MSAA", msaa.samples()),
+            },
+        }
+    }
+
+    // Where along its range the setting sits, from 0 to 1
+    fn fraction(self, settings: &Settings) -> f32 {
+        let (min, max) = self.range();
+        ((self.get(settings) - min) / (max - min)).clamp(0.0, 1.0)
+    }
+}
+
+// The clickable track of a slider
+#[derive(Component)]
+struct SliderTrack(SliderSetting);
+
+#[derive(Component)]
+struct SliderFill(SliderSetting);
+
+#[derive(Component)]
+struct SliderLabel(SliderSetting);
+
+fn spawn_pause_menu(mut commands: Commands, settings: Res<Settings>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|root| {
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(24.0)),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            })
+            .with_children(|panel| {
+                panel.spawn(TextBundle::from_section("Paused", TextStyle { font_size: 32.0, ..default() }));
+                spawn_button(panel, MenuButton::Resume, "Resume");
+                for setting in SliderSetting::ALL {
+                    spawn_slider(panel, setting, &settings);
+                }
+                spawn_button(panel, MenuButton::Quit, "Quit");
+            });
+        });
+}
+
+fn spawn_main_menu(mut commands: Commands, world_gen: Res<WorldGen>, mut entry: ResMut<SeedEntry>) {
+    entry.0 = world_gen.seed.to_string();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                // Opaque, since there is no world behind it yet
+                background_color: BACKDROP_COLOR.into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            MainMenu,
+        ))
+        .with_children(|root| {
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(24.0)),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            })
+            .with_children(|panel| {
+                panel.spawn(TextBundle::from_section("New world", TextStyle { font_size: 32.0, ..default() }));
+                panel.spawn(TextBundle::from_section("Seed", TextStyle { font_size: 16.0, ..default() }));
+                panel
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(TRACK_WIDTH),
+                            padding: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: TRACK_COLOR.into(),
+                        ..default()
+                    })
+                    .with_children(|field| {
+                        field.spawn((
+                            TextBundle::from_section(seed_label(&entry), TextStyle { font_size: 20.0, ..default() }),
+                            SeedText,
+                        ));
+                    });
+                spawn_button(panel, MainMenuButton::Random, "Random");
+                spawn_button(panel, MainMenuButton::Generate, "Generate");
+            });
+        });
+}
+
+// The field's text, with a caret where typing goes
+fn seed_label(entry: &SeedEntry) -> String {
+    format!("{}_", entry.0)
+}
+
+fn despawn_main_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Digits go into the field as long as it still holds a valid seed; Enter generates
+fn type_seed(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut char_events: EventReader<ReceivedCharacter>,
+    mut entry: ResMut<SeedEntry>,
+    mut world_gen: ResMut<WorldGen>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in char_events.read() {
+        if event.char.is_ascii_digit() && format!("{}{}", entry.0, event.char).parse::<u32>().is_ok() {
+            entry.0.push(event.char);
+        }
+    }
+    if input.just_pressed(KeyCode::Back) {
+        entry.0.pop();
+    }
+    if input.just_pressed(KeyCode::Return) {
+        generate_world(&mut commands, &entry, &mut world_gen, &mut next_state);
+    }
+}
+
+fn press_main_menu_buttons(
+    mut commands: Commands,
+    mut entry: ResMut<SeedEntry>,
+    mut world_gen: ResMut<WorldGen>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut button_query: Query<(&Interaction, &MainMenuButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in button_query.iter_mut() {
+        *color = button_color(*interaction).into();
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MainMenuButton::Random => entry.0 = rand::random::<u32>().to_string(),
+            MainMenuButton::Generate => generate_world(&mut commands, &entry, &mut world_gen, &mut next_state),
+        }
+    }
+}
+
+// Reseeds the generator and starts loading the world it makes; anything
+// left of another seed's world is torn down first
+fn generate_world(
+    commands: &mut Commands,
+    entry: &SeedEntry,
+    world_gen: &mut WorldGen,
+    next_state: &mut NextState<GameState>,
+) {
+    let seed = entry.0.parse().unwrap_or(world_gen.seed);
+    if seed != world_gen.seed {
+        *world_gen = world_gen.with_seed(seed);
+        commands.add(reset_world);
+    }
+    info!("Generating world with seed {seed}");
+    next_state.set(GameState::Loading);
+}
+
+fn update_seed_text(entry: Res<SeedEntry>, mut text_query: Query<&mut Text, With<SeedText>>) {
+    if !entry.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = seed_label(&entry);
+    }
+}
+
+fn spawn_button(parent: &mut ChildBuilder, button: impl Component, label: &str) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(TRACK_WIDTH),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(label, TextStyle { font_size: 20.0, ..default() }));
+        });
+}
+
+fn spawn_slider(parent: &mut ChildBuilder, setting: SliderSetting, settings: &Settings) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                TextBundle::from_section(setting.label(settings), TextStyle { font_size: 16.0, ..default() }),
+                SliderLabel(setting),
+            ));
+            row.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(TRACK_WIDTH),
+                        height: Val::Px(14.0),
+                        ..default()
+                    },
+                    background_color: TRACK_COLOR.into(),
+                    ..default()
+                },
+                RelativeCursorPosition::default(),
+                SliderTrack(setting),
+            ))
+            .with_children(|track| {
+                track.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(setting.fraction(settings) * 100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: FILL_COLOR.into(),
+                        ..default()
+                    },
+                    SliderFill(setting),
+                ));
+            });
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, menu_query: Query<Entity, With<PauseMenu>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn press_menu_buttons(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_events: EventWriter<AppExit>,
+    mut button_query: Query<(&Interaction, &MenuButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in button_query.iter_mut() {
+        *color = button_color(*interaction).into();
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MenuButton::Resume => next_state.set(GameState::Playing),
+            // Saving on exit is handled by the save plugin
+            MenuButton::Quit => exit_events.send(AppExit),
+        }
+    }
+}
+
+fn button_color(interaction: Interaction) -> Color {
+    match interaction {
+        Interaction::Pressed => BUTTON_PRESSED_COLOR,
+        Interaction::Hovered => BUTTON_HOVER_COLOR,
+        Interaction::None => BUTTON_COLOR,
+    }
+}
+
+// While a track is held, the setting follows the cursor along it
+fn drag_sliders(
+    mut settings: ResMut<Settings>,
+    track_query: Query<(&Interaction, &RelativeCursorPosition, &SliderTrack)>,
+) {
+    for (interaction, cursor, track) in track_query.iter() {
+        let (Interaction::Pressed, Some(position)) = (interaction, cursor.normalized) else {
+            continue;
+        };
+        let setting = track.0;
+        let (min, max) = setting.range();
+        let value = min + (max - min) * position.x.clamp(0.0, 1.0);
+        // Only touch the settings on a real change, so change detection stays quiet
+        if setting.get(&settings) != value {
+            setting.set(&mut settings, value);
+        }
+    }
+}
+
+fn update_sliders(
+    settings: Res<Settings>,
+    mut fill_query: Query<(&mut Style, &SliderFill)>,
+    mut label_query: Query<(&mut Text, &SliderLabel)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut style, fill) in fill_query.iter_mut() {
+        style.width = Val::Percent(fill.0.fraction(&settings) * 100.0);
+    }
+    for (mut text, label) in label_query.iter_mut() {
+        text.sections[0].value = label.0.label(&settings);
+    }
+}
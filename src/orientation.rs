@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexAttribute;
+use bevy::render::render_resource::VertexFormat;
+
+// Atlas tile of each face, one per vertex, for a material that samples the
+// block atlas; UVs stay in blocks so the shader wraps them into the tile
+pub const ATTRIBUTE_ATLAS_TILE: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_AtlasTile", 2_604_771_303, VertexFormat::Uint32);
+
+// One of the six directions a block face, or a directional block, can point.
+// Declared in `CUBE_FACES` order, and stored in a block's data byte by index.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Facing {
+    NegZ,
+    PosZ,
+    NegX,
+    PosX,
+    Up,
+    Down,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 6] = [Facing::NegZ, Facing::PosZ, Facing::NegX, Facing::PosX, Facing::Up, Facing::Down];
+
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Facing> {
+        Facing::ALL.get(index as usize).copied()
+    }
+
+    pub fn normal(self) -> IVec3 {
+        match self {
+            Facing::NegZ => IVec3::NEG_Z,
+            Facing::PosZ => IVec3::Z,
+            Facing::NegX => IVec3::NEG_X,
+            Facing::PosX => IVec3::X,
+            Facing::Up => IVec3::Y,
+            Facing::Down => IVec3::NEG_Y,
+        }
+    }
+
+    // The direction closest to `normal`, e.g. for faces of the smooth mesh
+    pub fn nearest(normal: Vec3) -> Facing {
+        let abs = normal.abs();
+        if abs.x > abs.y && abs.x > abs.z {
+            if normal.x > 0.0 { Facing::PosX } else { Facing::NegX }
+        } else if abs.y >= abs.z {
+            if normal.y > 0.0 { Facing::Up } else { Facing::Down }
+        } else if normal.z > 0.0 {
+            Facing::PosZ
+        } else {
+            Facing::NegZ
+        }
+    }
+
+    // Turned a quarter about the vertical, the same way `VoxelClipboard::rotate` turns
+    pub fn quarter_turn(self) -> Facing {
+        let normal = self.normal();
+        Facing::nearest(IVec3::new(-normal.z, normal.y, normal.x).as_vec3())
+    }
+
+    // 0, 1 or 2 for the x, y or z axis
+    pub fn axis(self) -> usize {
+        match self {
+            Facing::NegX | Facing::PosX => 0,
+            Facing::Up | Facing::Down => 1,
+            Facing::NegZ | Facing::PosZ => 2,
+        }
+    }
+
+    // World axes a face pointing this way maps to texture u and v
+    pub fn uv_axes(self) -> (usize, usize) {
+        match self.axis() {
+            0 => (2, 1),
+            1 => (0, 2),
+            _ => (0, 1),
+        }
+    }
+}
+
+// Tiles of the block atlas, in atlas order
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AtlasTile {
+    GrassTop,
+    GrassSide,
+    Dirt,
+    Stone,
+    CoalOre,
+    IronOre,
+    Water,
+    Bedrock,
+    Glowstone,
+    Leaves,
+    Sand,
+    LogEnd,
+    // Grain runs along v, so the tile needs a turn wherever a log's axis is a face's u
+    LogBark,
+    Glass,
+}
+
+// What the mesher draws on one face of a block
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FaceTexture {
+    pub tile: AtlasTile,
+    // Quarter turns of the tile
+    pub rotation: u8,
+}
+
+impl FaceTexture {
+    pub fn new(tile: AtlasTile) -> Self {
+        Self { tile, rotation: 0 }
+    }
+
+    // Turns UVs spanning `[0, width] x [0, height]` so they still tile from zero
+    pub fn rotate_uvs(self, uvs: [[f32; 2]; 4]) -> [[f32; 2]; 4] {
+        (0..self.rotation % 4).fold(uvs, |uvs, _| {
+            let width = uvs.iter().map(|uv| uv[0]).fold(0.0, f32::max);
+            uvs.map(|[u, v]| [v, width - u])
+        })
+    }
+}
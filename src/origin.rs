@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::{chunk_to_world_origin, world_to_chunk, Chunk, Player};
+
+// Distance from the render origin, in blocks, at which the world is shifted back
+const REBASE_DISTANCE: f32 = 1024.0;
+
+// Maps render space, where transforms live, to voxel space. Transforms are
+// kept relative to `chunk` so f32 positions stay precise however far the
+// player travels (a floating origin), and one voxel spans `voxel_scale`
+// render units. Voxel and chunk coordinates are always absolute; convert
+// between the two spaces with these helpers.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct WorldOrigin {
+    // Chunk the render origin sits at; `y` is always zero
+    pub chunk: IVec3,
+    // Mirrors `Settings::voxel_scale` once `apply_voxel_scale` has run
+    pub voxel_scale: f32,
+}
+
+// Starts at the spawn, so the player begins next to the render origin
+impl FromWorld for WorldOrigin {
+    fn from_world(world: &mut World) -> Self {
+        let settings = world.get_resource::<Settings>();
+        let spawn = settings.and_then(|settings| settings.spawn).unwrap_or(Vec3::ZERO);
+        Self {
+            chunk: world_to_chunk(spawn.floor().as_ivec3()) * IVec3::new(1, 0, 1),
+            voxel_scale: settings.map_or(1.0, |settings| settings.voxel_scale),
+        }
+    }
+}
+
+impl WorldOrigin {
+    // Absolute block position of the render origin
+    pub fn block_offset(&self) -> IVec3 {
+        chunk_to_world_origin(self.chunk)
+    }
+
+    // A render-space position in blocks, still relative to the origin
+    pub fn to_voxel_space(&self, position: Vec3) -> Vec3 {
+        position / self.voxel_scale
+    }
+
+    // Absolute voxel containing a render-space position
+    pub fn voxel_at(&self, position: Vec3) -> IVec3 {
+        self.to_voxel_space(position).floor().as_ivec3() + self.block_offset()
+    }
+
+    // Absolute chunk containing a render-space position
+    pub fn chunk_at(&self, position: Vec3) -> IVec3 {
+        world_to_chunk(self.voxel_at(position))
+    }
+
+    // Render-space corner of an absolute voxel
+    pub fn to_render(&self, voxel: IVec3) -> Vec3 {
+        (voxel - self.block_offset()).as_vec3() * self.voxel_scale
+    }
+
+    // Absolute position in blocks for display, where precision no longer matters
+    pub fn to_world(&self, position: Vec3) -> Vec3 {
+        self.to_voxel_space(position) + self.block_offset().as_vec3()
+    }
+}
+
+// Top-level world entities; children and UI follow their parents
+#[cfg(not(feature = "headless"))]
+type RootEntities = (Without<Player>, Without<Parent>, Without<Node>);
+#[cfg(feature = "headless")]
+type RootEntities = (Without<Player>, Without<Parent>);
+
+// Once the player strays too far, moves every root entity back by the
+// player's chunk offset, so chunk meshes and the camera stay near zero
+pub fn rebase_origin(
+    mut origin: ResMut<WorldOrigin>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    mut root_query: Query<&mut Transform, RootEntities>,
+) {
+    let Ok(mut player_transform) = player_query.get_single_mut() else {
+        return;
+    };
+    let position = origin.to_voxel_space(player_transform.translation);
+    if position.xz().abs().max_element() < REBASE_DISTANCE {
+        return;
+    }
+
+    let shift = world_to_chunk(position.floor().as_ivec3()) * IVec3::new(1, 0, 1);
+    origin.chunk += shift;
+    let offset = chunk_to_world_origin(shift).as_vec3() * origin.voxel_scale;
+    player_transform.translation -= offset;
+    for mut transform in root_query.iter_mut() {
+        transform.translation -= offset;
+    }
+    info!("Rebased world origin to chunk {}", origin.chunk);
+}
+
+// Resizes the world around the render origin when `Settings::voxel_scale`
+// changes, keeping the player over the same voxel
+pub fn apply_voxel_scale(
+    settings: Res<Settings>,
+    mut origin: ResMut<WorldOrigin>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+    mut root_query: Query<(&mut Transform, Has<Chunk>), RootEntities>,
+) {
+    if settings.voxel_scale == origin.voxel_scale {
+        return;
+    }
+
+    let ratio = settings.voxel_scale / origin.voxel_scale;
+    origin.voxel_scale = settings.voxel_scale;
+    for mut transform in player_query.iter_mut() {
+        transform.translation *= ratio;
+    }
+    for (mut transform, is_chunk) in root_query.iter_mut() {
+        transform.translation *= ratio;
+        if is_chunk {
+            transform.scale = Vec3::splat(settings.voxel_scale);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+#[cfg(not(feature = "headless"))]
+use bevy::window::PrimaryWindow;
+
+use crate::settings::Settings;
+
+pub struct FramePacingPlugin;
+
+impl Plugin for FramePacingPlugin {
+    fn build(&self, app: &mut App) {
+        #[cfg(not(feature = "headless"))]
+        app.add_systems(Update, apply_present_mode);
+
+        app.add_systems(Last, limit_frame_rate);
+    }
+}
+
+// Also runs on the first frame, so the window starts with the configured mode
+#[cfg(not(feature = "headless"))]
+fn apply_present_mode(settings: Res<Settings>, mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut window in window_query.iter_mut() {
+        if window.present_mode != settings.present_mode {
+            window.present_mode = settings.present_mode;
+        }
+    }
+}
+
+// Sleeps out the rest of the frame when it finished early. Runs last, so the
+// wait covers everything the frame did; with vsync on, the tighter of the two
+// limits wins.
+fn limit_frame_rate(settings: Res<Settings>, mut last_frame: Local<Option<Instant>>) {
+    let now = Instant::now();
+    if let (Some(max_fps), Some(last)) = (settings.max_fps.filter(|&fps| fps > 0.0), *last_frame) {
+        let frame = Duration::from_secs_f32(1.0 / max_fps);
+        if let Some(remaining) = frame.checked_sub(now - last) {
+            std::thread::sleep(remaining);
+        }
+    }
+    *last_frame = Some(Instant::now());
+}
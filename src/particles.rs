@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{BlockChanged, BlockType, WorldOrigin};
+
+const PARTICLE_SIZE: f32 = 0.12;
+const PARTICLE_GRAVITY: f32 = 15.0;
+const PARTICLE_SPEED: f32 = 3.0;
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BreakParticles>()
+            .add_systems(Update, (spawn_break_particles, update_particles));
+    }
+}
+
+// Tuning for the burst spawned when a block is broken
+#[derive(Resource)]
+pub struct BreakParticles {
+    pub count: usize,
+    // Seconds before a particle despawns
+    pub lifetime: f32,
+}
+
+impl Default for BreakParticles {
+    fn default() -> Self {
+        Self {
+            count: 12,
+            lifetime: 0.6,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+// Shared cube mesh plus one material per block color, created on first use
+#[derive(Default)]
+struct ParticleAssets {
+    mesh: Option<Handle<Mesh>>,
+    materials: HashMap<BlockType, Handle<StandardMaterial>>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_break_particles(
+    mut commands: Commands,
+    mut events: EventReader<BlockChanged>,
+    config: Res<BreakParticles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    world_origin: Res<WorldOrigin>,
+    mut assets: Local<ParticleAssets>,
+    mut rng: Local<u32>,
+) {
+    for event in events.read() {
+        if event.new != BlockType::Air || !event.old.is_solid() {
+            continue;
+        }
+
+        let mesh = assets
+            .mesh
+            .get_or_insert_with(|| meshes.add(shape::Cube::new(PARTICLE_SIZE).into()))
+            .clone();
+        let material = assets
+            .materials
+            .entry(event.old)
+            .or_insert_with(|| materials.add(event.old.color().into()))
+            .clone();
+        let center = world_origin.to_render(event.world_pos) + Vec3::splat(0.5 * world_origin.voxel_scale);
+
+        for _ in 0..config.count {
+            let offset = Vec3::new(next_random(&mut rng), next_random(&mut rng), next_random(&mut rng)) - 0.5;
+            commands.spawn((
+                PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(center + offset * 0.8 * world_origin.voxel_scale),
+                    ..default()
+                },
+                Particle {
+                    // Mostly outwards, with an upward pop
+                    velocity: (offset + Vec3::Y * 0.6) * PARTICLE_SPEED,
+                    timer: Timer::from_seconds(config.lifetime, TimerMode::Once),
+                },
+            ));
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut transform) in query.iter_mut() {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        particle.velocity.y -= PARTICLE_GRAVITY * dt;
+        transform.translation += particle.velocity * dt;
+        transform.scale = Vec3::splat(particle.timer.percent_left());
+    }
+}
+
+// Xorshift; particles only need cheap jitter, not quality randomness
+fn next_random(state: &mut u32) -> f32 {
+    if *state == 0 {
+        *state = 0x9e37_79b9;
+    }
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    (*state >> 8) as f32 / (1 << 24) as f32
+}
@@ -0,0 +1,605 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::CameraModePlugin;
+use crate::head_bob::HeadBobPlugin;
+use crate::settings::{Settings, VoidRescue};
+use crate::{
+    spawn_point, ActionState, BlockType, CursorGrabbed, GameState, PlayerAction, WorldGen, WorldMap, WorldOrigin,
+    EMPTY_SHAPE, FULL_SHAPE, SEA_LEVEL,
+};
+
+pub const WALK_SPEED: f32 = 5.0;
+const FLY_SPEED: f32 = 10.0;
+const SPRINT_MULTIPLIER: f32 = 1.6;
+pub const EYE_HEIGHT: f32 = 1.6;
+pub const PLAYER_HEIGHT: f32 = 1.8;
+pub const PLAYER_HALF_WIDTH: f32 = 0.3;
+// Longest single move checked for collisions, in blocks, kept well under one
+const MAX_COLLISION_STEP: f32 = 0.25;
+// Largest mouse movement applied in one frame, in pixels; anything past this
+// is a burst of stale motion rather than a real flick
+const MAX_LOOK_DELTA: f32 = 250.0;
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((HeadBobPlugin, CameraModePlugin)).init_resource::<PhysicsConfig>().add_systems(
+            Update,
+            (follow_gravity, player_look, toggle_movement_mode, player_movement, rescue_from_void, update_fov)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Player;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MovementMode {
+    #[default]
+    Walk,
+    // Flies through everything
+    Fly,
+    // Flies without gravity but still stops at solid blocks
+    FlyCollide,
+}
+
+// How quickly horizontal velocity ramps towards the input direction (`accel`)
+// and bleeds off with no input (`friction`), in blocks per second squared
+#[derive(Clone, Copy, Debug)]
+pub struct Traction {
+    pub accel: f32,
+    pub friction: f32,
+}
+
+// World-wide physics tuning, e.g. lower `gravity` for a moon-like feel
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PhysicsConfig {
+    // Acceleration along `gravity_direction`, in blocks per second squared
+    pub gravity: f32,
+    // Which way gravity pulls. Snapped to the nearest axis, since the player's
+    // box and the voxel grid are both axis aligned.
+    pub gravity_direction: Vec3,
+    // Height a jump reaches on flat ground, in blocks
+    pub jump_height: f32,
+    // Fastest the player can fall, in blocks per second
+    pub terminal_velocity: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            gravity: 25.0,
+            gravity_direction: Vec3::NEG_Y,
+            jump_height: 1.3,
+            terminal_velocity: 50.0,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    // Upward speed that reaches `jump_height` under `gravity`
+    pub fn jump_speed(&self) -> f32 {
+        (2.0 * self.gravity * self.jump_height).sqrt()
+    }
+
+    // Unit vector against gravity along the nearest axis; straight up if unset
+    pub fn up(&self) -> Vec3 {
+        let down = self.gravity_direction;
+        let abs = down.abs();
+        if abs.x > abs.y && abs.x > abs.z {
+            Vec3::new(-down.x.signum(), 0.0, 0.0)
+        } else if abs.z > abs.y {
+            Vec3::new(0.0, 0.0, -down.z.signum())
+        } else if down.y > 0.0 {
+            Vec3::NEG_Y
+        } else {
+            Vec3::Y
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PlayerPhysics {
+    pub velocity: Vec3,
+    pub grounded: bool,
+    pub sprinting: bool,
+    // Extra jumps allowed before touching the ground again
+    pub max_jumps: u32,
+    pub air_jumps_used: u32,
+    pub ground: Traction,
+    pub air: Traction,
+    pub water: Traction,
+    // Against gravity, as last taken from `PhysicsConfig::up`; the player's
+    // box stands along it and jumps follow it
+    pub up: Vec3,
+}
+
+impl Default for PlayerPhysics {
+    fn default() -> Self {
+        Self {
+            velocity: Vec3::ZERO,
+            grounded: false,
+            sprinting: false,
+            max_jumps: 1,
+            air_jumps_used: 0,
+            ground: Traction { accel: 50.0, friction: 40.0 },
+            air: Traction { accel: 12.0, friction: 2.0 },
+            water: Traction { accel: 20.0, friction: 10.0 },
+            up: Vec3::Y,
+        }
+    }
+}
+
+impl PlayerPhysics {
+    // Velocity across gravity, i.e. walking rather than falling or jumping
+    pub fn horizontal_velocity(&self) -> Vec3 {
+        self.velocity - self.up * self.velocity.dot(self.up)
+    }
+}
+
+// Rotation taking +Y onto `up`; look yaw and pitch are measured within it
+fn gravity_frame(up: Vec3) -> Quat {
+    Quat::from_rotation_arc(Vec3::Y, up)
+}
+
+// Turns the player to stand along a changed gravity, keeping the feet in
+// place and the view direction as close as the new up allows
+fn follow_gravity(
+    config: Res<PhysicsConfig>,
+    world_origin: Res<WorldOrigin>,
+    mut query: Query<(&mut Transform, &mut PlayerPhysics), With<Player>>,
+) {
+    let up = config.up();
+    for (mut transform, mut physics) in query.iter_mut() {
+        if physics.up == up {
+            continue;
+        }
+        let (yaw, pitch, _) = (gravity_frame(physics.up).inverse() * transform.rotation).to_euler(EulerRot::YXZ);
+        transform.rotation = gravity_frame(up) * Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+        transform.translation += (up - physics.up) * EYE_HEIGHT * world_origin.voxel_scale;
+        physics.up = up;
+        physics.grounded = false;
+    }
+}
+
+// Safety net for a walking player who ended up under the world, e.g. after
+// loading into a carved out region, and would otherwise fall forever
+fn rescue_from_void(
+    settings: Res<Settings>,
+    world_gen: Res<WorldGen>,
+    world_map: Res<WorldMap>,
+    world_origin: Res<WorldOrigin>,
+    mut query: Query<(&mut Transform, &mut PlayerPhysics, &MovementMode), With<Player>>,
+) {
+    for (mut transform, mut physics, &mode) in query.iter_mut() {
+        let fallen = world_origin.to_world(transform.translation) - physics.up * EYE_HEIGHT;
+        if mode != MovementMode::Walk || fallen.y >= world_gen.bottom_y as f32 - settings.void_margin {
+            continue;
+        }
+
+        let feet = match settings.void_rescue {
+            VoidRescue::Surface => {
+                let column = fallen.floor().as_ivec3();
+                // Loaded columns may have been built on; unloaded ones are as generated
+                let top = (world_gen.bottom_y..=world_gen.top_y)
+                    .rev()
+                    .map(|y| (y, world_map.voxel(IVec3::new(column.x, y, column.z))))
+                    .find(|(_, block)| block.is_none_or(BlockType::is_solid))
+                    .map_or(world_gen.bottom_y, |(y, block)| match block {
+                        Some(_) => y + 1,
+                        None => world_gen.surface_height_at(column.x, column.z).max(SEA_LEVEL),
+                    });
+                Vec3::new(fallen.x, top as f32, fallen.z)
+            }
+            VoidRescue::Spawn => spawn_point(&settings, &world_gen),
+        };
+        let eye = feet + physics.up * EYE_HEIGHT - world_origin.block_offset().as_vec3();
+        transform.translation = eye * world_origin.voxel_scale;
+        physics.velocity = Vec3::ZERO;
+        physics.grounded = false;
+        warn!("Player fell out of the world at {fallen}, moved back to {feet}");
+    }
+}
+
+fn player_look(
+    settings: Res<Settings>,
+    grabbed: Res<CursorGrabbed>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut query: Query<(&mut Transform, &PlayerPhysics), With<Player>>,
+) {
+    // Always drain the raw events so switching modes doesn't replay stale motion
+    let raw_delta: Vec2 = motion_events.read().map(|motion| motion.delta).sum();
+    // Motion from the frame the cursor was grabbed built up while it was free
+    if !grabbed.0 || grabbed.is_changed() {
+        return;
+    }
+    let delta = if settings.raw_mouse_input {
+        raw_delta
+    } else {
+        windows.get_single_mut().map_or(Vec2::ZERO, |mut window| pointer_delta(&mut window))
+    }
+    .clamp_length_max(MAX_LOOK_DELTA);
+    if delta == Vec2::ZERO {
+        return;
+    }
+    let Ok((mut player_transform, physics)) = query.get_single_mut() else {
+        return;
+    };
+
+    // Inverting before the clamp keeps an inverted pitch within the same limits
+    let invert = Vec2::new(flip(settings.invert_x), flip(settings.invert_y));
+    let delta = delta * invert * settings.mouse_sensitivity;
+    let frame = gravity_frame(physics.up);
+    let (yaw, pitch, _) = (frame.inverse() * player_transform.rotation).to_euler(EulerRot::YXZ);
+    let yaw = yaw - delta.x;
+    let pitch = (pitch - delta.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    player_transform.rotation = frame * Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+}
+
+fn flip(inverted: bool) -> f32 {
+    if inverted {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+// How far the pointer moved from the window center since last frame, then
+// warps it back so it never reaches the edge of the confined window
+fn pointer_delta(window: &mut Window) -> Vec2 {
+    let Some(position) = window.cursor_position() else {
+        return Vec2::ZERO;
+    };
+    let center = Vec2::new(window.width(), window.height()) / 2.0;
+    window.set_cursor_position(Some(center));
+    position - center
+}
+
+fn toggle_movement_mode(
+    actions: Res<ActionState>,
+    mut query: Query<(&mut MovementMode, &mut PlayerPhysics), With<Player>>,
+) {
+    if !actions.just_pressed(PlayerAction::ToggleFly) {
+        return;
+    }
+    for (mut mode, mut physics) in query.iter_mut() {
+        *mode = match *mode {
+            MovementMode::Walk => MovementMode::Fly,
+            MovementMode::Fly => MovementMode::FlyCollide,
+            MovementMode::FlyCollide => MovementMode::Walk,
+        };
+        physics.velocity = Vec3::ZERO;
+    }
+}
+
+fn player_movement(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    world_map: Res<WorldMap>,
+    world_gen: Res<WorldGen>,
+    world_origin: Res<WorldOrigin>,
+    config: Res<PhysicsConfig>,
+    mut query: Query<(&mut Transform, &mut PlayerPhysics, &MovementMode), With<Player>>,
+) {
+    let Ok((mut player_transform, mut physics, mode)) = query.get_single_mut() else {
+        return;
+    };
+    let dt = time.delta_seconds();
+    let speed_multiplier = if physics.sprinting { SPRINT_MULTIPLIER } else { 1.0 };
+
+    if *mode != MovementMode::Walk {
+        let mut direction = Vec3::ZERO;
+
+        if actions.pressed(PlayerAction::MoveForward) {
+            direction += player_transform.forward();
+        }
+        if actions.pressed(PlayerAction::MoveBack) {
+            direction += player_transform.back();
+        }
+        if actions.pressed(PlayerAction::MoveLeft) {
+            direction += player_transform.left();
+        }
+        if actions.pressed(PlayerAction::MoveRight) {
+            direction += player_transform.right();
+        }
+        if actions.pressed(PlayerAction::Jump) {
+            direction += physics.up;
+        }
+        if actions.pressed(PlayerAction::Descend) {
+            direction -= physics.up;
+        }
+
+        physics.sprinting = actions.pressed(PlayerAction::Sprint) && direction != Vec3::ZERO;
+        let velocity = direction.normalize_or_zero() * FLY_SPEED * speed_multiplier;
+        if *mode == MovementMode::Fly {
+            player_transform.translation += velocity * dt;
+        } else {
+            physics.velocity = velocity;
+            let eye = player_transform.translation;
+            player_transform.translation =
+                resolve_collisions(&world_map, &world_gen, &world_origin, &mut physics, eye, dt);
+        }
+        return;
+    }
+
+    // Walking: steer on the plane across gravity only
+    let up = physics.up;
+    let forward = player_transform.forward();
+    let forward = (forward - up * forward.dot(up)).normalize_or_zero();
+    let right = forward.cross(up);
+    let mut direction = Vec3::ZERO;
+
+    if actions.pressed(PlayerAction::MoveForward) {
+        direction += forward;
+    }
+    if actions.pressed(PlayerAction::MoveBack) {
+        direction -= forward;
+    }
+    if actions.pressed(PlayerAction::MoveLeft) {
+        direction -= right;
+    }
+    if actions.pressed(PlayerAction::MoveRight) {
+        direction += right;
+    }
+
+    // Sprint only while moving forward, and keep it until the player stops
+    if actions.pressed(PlayerAction::Sprint) && actions.pressed(PlayerAction::MoveForward) {
+        physics.sprinting = true;
+    } else if !actions.pressed(PlayerAction::MoveForward) {
+        physics.sprinting = false;
+    }
+
+    let body_offset = (EYE_HEIGHT - PLAYER_HEIGHT / 2.0) * world_origin.voxel_scale;
+    let body = world_origin.voxel_at(player_transform.translation - up * body_offset);
+    // Hold still until the column under the player has been generated
+    let column = IVec3::new(body.x, body.y.clamp(world_gen.bottom_y, world_gen.top_y), body.z);
+    if world_map.voxel(column).is_none() {
+        physics.velocity = Vec3::ZERO;
+        return;
+    }
+
+    let traction = if world_map.voxel(body).is_some_and(BlockType::is_water) {
+        physics.water
+    } else if physics.grounded {
+        physics.ground
+    } else {
+        physics.air
+    };
+    let target = direction.normalize_or_zero() * WALK_SPEED * speed_multiplier;
+    let rate = if target == Vec3::ZERO { traction.friction } else { traction.accel };
+    let horizontal = approach(physics.horizontal_velocity(), target, rate * dt);
+    let mut rise = (physics.velocity.dot(up) - config.gravity * dt).max(-config.terminal_velocity);
+
+    if actions.just_pressed(PlayerAction::Jump) {
+        if physics.grounded {
+            rise = config.jump_speed();
+        } else if physics.air_jumps_used < physics.max_jumps {
+            rise = config.jump_speed();
+            physics.air_jumps_used += 1;
+        }
+    }
+    physics.velocity = horizontal + up * rise;
+
+    player_transform.translation =
+        resolve_collisions(&world_map, &world_gen, &world_origin, &mut physics, player_transform.translation, dt);
+    if physics.grounded {
+        physics.air_jumps_used = 0;
+    }
+}
+
+// Moves the player's eye by its velocity, one axis at a time so it slides
+// along walls, and zeroes velocity on any axis that hits a solid block.
+// Each axis is swept in steps shorter than a block, so a fast fall can't
+// skip over a thin floor. `eye` is in render space, and the player stands
+// along `physics.up`.
+fn resolve_collisions(
+    world_map: &WorldMap,
+    world_gen: &WorldGen,
+    world_origin: &WorldOrigin,
+    physics: &mut PlayerPhysics,
+    eye: Vec3,
+    dt: f32,
+) -> Vec3 {
+    let (up, scale) = (physics.up, world_origin.voxel_scale);
+    let mut feet = eye - up * EYE_HEIGHT * scale;
+    physics.grounded = false;
+    let max_step = MAX_COLLISION_STEP * scale;
+    for axis in 0..3 {
+        let distance = physics.velocity[axis] * dt;
+        let steps = (distance.abs() / max_step).ceil().max(1.0);
+        for _ in 0..steps as u32 {
+            let mut moved = feet;
+            moved[axis] += distance / steps;
+
+            if check_collision(world_map, world_gen, world_origin, moved, up) {
+                // Landing is hitting something while moving with gravity
+                if physics.velocity[axis] * up[axis] < 0.0 {
+                    physics.grounded = true;
+                }
+                physics.velocity[axis] = 0.0;
+                break;
+            }
+            feet = moved;
+        }
+    }
+
+    feet + up * EYE_HEIGHT * scale
+}
+
+// Moves `current` towards `target` by at most `max_delta` without overshooting
+fn approach(current: Vec3, target: Vec3, max_delta: f32) -> Vec3 {
+    let delta = target - current;
+    if delta.length() <= max_delta {
+        target
+    } else {
+        current + delta.normalize() * max_delta
+    }
+}
+
+// The player's box standing at `feet`, its height running along `up`. The
+// player is sized in blocks, so `scale` render units per voxel sizes the box.
+fn player_bounds(feet: Vec3, up: Vec3, scale: f32) -> (Vec3, Vec3) {
+    let width = Vec3::splat(PLAYER_HALF_WIDTH * scale) * (Vec3::ONE - up.abs());
+    let (near, far) = (feet - width, feet + width + up * PLAYER_HEIGHT * scale);
+    (near.min(far), near.max(far))
+}
+
+// Whether a block placed at `voxel` would intersect a player whose eyes are
+// at `eye`, standing along `up`
+pub fn overlaps_player(eye: Vec3, up: Vec3, world_origin: &WorldOrigin, voxel: IVec3) -> bool {
+    let scale = world_origin.voxel_scale;
+    let (min, max) = player_bounds(eye - up * EYE_HEIGHT * scale, up, scale);
+    let corner = world_origin.to_render(voxel);
+    min.cmplt(corner + Vec3::splat(scale)).all() && max.cmpgt(corner).all()
+}
+
+// Whether the player's bounding box, standing at `feet`, overlaps the collision
+// shape of any voxel. Voxels of chunks not generated yet count as full blocks,
+// so the player can't move into terrain before it exists and then get pushed
+// out once it loads.
+fn check_collision(
+    world_map: &WorldMap,
+    world_gen: &WorldGen,
+    world_origin: &WorldOrigin,
+    feet: Vec3,
+    up: Vec3,
+) -> bool {
+    let (min, max) = player_bounds(feet, up, world_origin.voxel_scale);
+    let min_voxel = world_origin.voxel_at(min);
+    let max_voxel = world_origin.voxel_at(max - Vec3::splat(0.001));
+
+    for x in min_voxel.x..=max_voxel.x {
+        for y in min_voxel.y..=max_voxel.y {
+            for z in min_voxel.z..=max_voxel.z {
+                let voxel = IVec3::new(x, y, z);
+                let shape = match world_map.voxel(voxel) {
+                    Some(block) => block.collision_shape(),
+                    // Nothing is ever generated outside the world's height
+                    None if world_gen.in_bounds(y) => FULL_SHAPE,
+                    None => EMPTY_SHAPE,
+                };
+                let corner = world_origin.to_render(voxel);
+                if shape.iter().any(|aabb| aabb.overlaps(corner, world_origin.voxel_scale, min, max)) {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+// Owns the camera FOV: the configured base plus an eased sprint kick
+fn update_fov(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut kick: Local<f32>,
+    mut query: Query<(&PlayerPhysics, &mut Projection), With<Player>>,
+) {
+    let Ok((physics, mut projection)) = query.get_single_mut() else {
+        return;
+    };
+    let target = if physics.sprinting { settings.sprint_fov_kick } else { 0.0 };
+    let blend = (settings.fov_kick_speed * time.delta_seconds()).min(1.0);
+    *kick += (target - *kick) * blend;
+
+    let fov = (settings.fov + *kick).to_radians();
+    if matches!(&*projection, Projection::Perspective(perspective) if perspective.fov != fov) {
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = fov;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkData;
+
+    // Chunk zero holding a stone slab and, beside it, a full stone block
+    fn slab_world() -> WorldMap {
+        let mut chunk = ChunkData::new();
+        chunk.set(IVec3::new(1, 1, 1), BlockType::StoneSlab);
+        chunk.set(IVec3::new(3, 1, 1), BlockType::Stone);
+        let mut world_map = WorldMap::default();
+        world_map.chunks.insert(IVec3::ZERO, chunk);
+        world_map
+    }
+
+    fn origin() -> WorldOrigin {
+        WorldOrigin { chunk: IVec3::ZERO, voxel_scale: 1.0 }
+    }
+
+    #[test]
+    fn half_slabs_only_collide_below_their_middle() {
+        let (world_map, world_gen, origin) = (slab_world(), WorldGen::new(0), origin());
+        let collides = |feet: Vec3| check_collision(&world_map, &world_gen, &origin, feet, Vec3::Y);
+        assert!(!collides(Vec3::new(1.5, 1.6, 1.5)));
+        assert!(!collides(Vec3::new(1.5, 1.5, 1.5)), "standing on the slab's top isn't colliding");
+        assert!(collides(Vec3::new(1.5, 1.4, 1.5)));
+        // The full block beside it still fills its whole voxel
+        assert!(collides(Vec3::new(3.5, 1.6, 1.5)));
+        assert!(!collides(Vec3::new(3.5, 2.0, 1.5)));
+    }
+
+    #[test]
+    fn falling_onto_a_half_slab_lands_on_its_top() {
+        let (world_map, world_gen, origin) = (slab_world(), WorldGen::new(0), origin());
+        let mut physics = PlayerPhysics::default();
+        let mut eye = Vec3::new(1.5, 3.0 + EYE_HEIGHT, 1.5);
+        for _ in 0..60 {
+            // Gravity would keep pulling every frame
+            physics.velocity = Vec3::NEG_Y * 5.0;
+            eye = resolve_collisions(&world_map, &world_gen, &origin, &mut physics, eye, 1.0 / 60.0);
+        }
+        let feet = eye.y - EYE_HEIGHT;
+        assert!(physics.grounded);
+        assert!((1.5..1.5 + MAX_COLLISION_STEP).contains(&feet), "feet came to rest at {feet}");
+    }
+
+    #[test]
+    fn the_player_keeps_its_size_in_blocks_at_any_voxel_scale() {
+        let (world_map, world_gen) = (slab_world(), WorldGen::new(0));
+        for scale in [0.5, 2.0] {
+            let origin = WorldOrigin { voxel_scale: scale, ..origin() };
+            let mut physics = PlayerPhysics::default();
+            let mut eye = Vec3::new(1.5, 3.0 + EYE_HEIGHT, 1.5) * scale;
+            for _ in 0..120 {
+                physics.velocity = Vec3::NEG_Y * 5.0 * scale;
+                eye = resolve_collisions(&world_map, &world_gen, &origin, &mut physics, eye, 1.0 / 60.0);
+            }
+            let feet = eye.y / scale - EYE_HEIGHT;
+            assert!((1.5..1.5 + MAX_COLLISION_STEP).contains(&feet), "feet came to rest at {feet} at scale {scale}");
+        }
+    }
+
+    // At terminal velocity a single long frame moves the player 25 blocks,
+    // far past a floor one block thick, but the sweep still stops them on it
+    #[test]
+    fn falling_at_terminal_velocity_lands_on_a_thin_floor() {
+        let mut chunk = ChunkData::new();
+        for (x, z) in (0..4).flat_map(|x| (0..4).map(move |z| (x, z))) {
+            chunk.set(IVec3::new(x, 1, z), BlockType::Stone);
+        }
+        let mut world_map = WorldMap::default();
+        world_map.chunks.insert(IVec3::ZERO, chunk);
+        world_map.chunks.insert(IVec3::NEG_Y, ChunkData::new());
+        let (world_gen, origin) = (WorldGen::new(0), origin());
+
+        let velocity = Vec3::NEG_Y * PhysicsConfig::default().terminal_velocity;
+        let mut physics = PlayerPhysics { velocity, ..default() };
+        let eye = Vec3::new(1.5, 14.0 + EYE_HEIGHT, 1.5);
+        let feet = resolve_collisions(&world_map, &world_gen, &origin, &mut physics, eye, 0.5).y - EYE_HEIGHT;
+        assert!(physics.grounded);
+        // Give or take the rounding of the eye height
+        assert!((2.0 - 1e-4..2.0 + MAX_COLLISION_STEP).contains(&feet), "feet came to rest at {feet}");
+    }
+}
@@ -0,0 +1,337 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::head_bob::remove_head_bob;
+use crate::interaction::Hotbar;
+use crate::player::EYE_HEIGHT;
+use crate::save::{decode_chunk, decode_player, encode_chunk, encode_player, PlayerState, SaveError};
+use crate::settings::Settings;
+use crate::{
+    preload_spawn_chunks, spawn_camera, world_to_chunk, ChunkData, ChunkIndex, GameState, MovementMode, Player,
+    PlayerPhysics, WorldMap, WorldOrigin,
+};
+
+// Chunk columns along each side of a region file
+pub const REGION_SIZE: i32 = 16;
+const REGION_MAGIC: &[u8; 4] = b"VXRG";
+const REGION_VERSION: u16 = 1;
+const HEADER_LEN: usize = REGION_MAGIC.len() + 2 + 4;
+// Chunk position, then offset and length of its blob in the data section
+const ENTRY_LEN: usize = 4 * 5;
+const PLAYER_FILE: &str = "player.dat";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldSave>()
+            // The spawn has to be known before the world around it starts loading
+            .add_systems(Startup, load_player.before(spawn_camera).before(preload_spawn_chunks))
+            .add_systems(PostStartup, restore_player)
+            .add_systems(Update, save_on_key)
+            // The saved eye is the steady one, not the bobbing camera
+            // Nothing to save before a world has been picked
+            .add_systems(Last, save_on_exit.after(remove_head_bob).run_if(not(in_state(GameState::MainMenu))));
+    }
+}
+
+// Edited chunks are stored in region files grouping `REGION_SIZE`² chunk
+// columns, so a large explored world stays a handful of files. Untouched
+// chunks are never saved; they regenerate identically from the seed.
+#[derive(Resource)]
+pub struct WorldSave {
+    pub dir: PathBuf,
+    // Encoded chunks of every region read or written so far
+    regions: HashMap<IVec2, Region>,
+}
+
+impl Default for WorldSave {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("saves/world"),
+            regions: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Region {
+    chunks: HashMap<IVec3, Vec<u8>>,
+    // Holds chunks not yet written to disk
+    dirty: bool,
+}
+
+fn region_of(chunk_pos: IVec3) -> IVec2 {
+    chunk_pos.xz().div_euclid(IVec2::splat(REGION_SIZE))
+}
+
+fn region_path(dir: &Path, region: IVec2) -> PathBuf {
+    dir.join(format!("r.{}.{}.region", region.x, region.y))
+}
+
+impl WorldSave {
+    // Reads a region file the first time one of its chunks is needed
+    fn region(&mut self, region: IVec2) -> &mut Region {
+        let path = region_path(&self.dir, region);
+        self.regions.entry(region).or_insert_with(|| match fs::read(&path) {
+            Ok(bytes) => parse_region(&bytes).unwrap_or_else(|err| {
+                warn!("Ignoring region file {}: {err}", path.display());
+                Region::default()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Region::default(),
+            Err(err) => {
+                warn!("Could not read region file {}: {err}", path.display());
+                Region::default()
+            }
+        })
+    }
+
+    // The saved copy of a chunk, if it was ever edited
+    pub fn load_chunk(&mut self, position: IVec3) -> Option<ChunkData> {
+        let bytes = self.region(region_of(position)).chunks.get(&position)?;
+        decode_chunk(bytes)
+            .map_err(|err| warn!("Discarding saved chunk {position}: {err}"))
+            .ok()
+    }
+
+    // The player as last saved, if there is a valid player file
+    pub fn load_player(&self) -> Option<PlayerState> {
+        let path = self.dir.join(PLAYER_FILE);
+        match fs::read(&path) {
+            Ok(bytes) => decode_player(&bytes)
+                .map_err(|err| warn!("Ignoring player file {}: {err}", path.display()))
+                .ok(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Could not read player file {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    // Keeps an edited chunk for the next `save_world`, e.g. when it unloads
+    pub fn store_chunk(&mut self, position: IVec3, chunk: &ChunkData) {
+        let region = self.region(region_of(position));
+        region.chunks.insert(position, encode_chunk(chunk));
+        region.dirty = true;
+    }
+
+    // Forgets the saved copy of a chunk, so it generates afresh from now on
+    pub fn discard_chunk(&mut self, position: IVec3) {
+        let region = self.region(region_of(position));
+        if region.chunks.remove(&position).is_some() {
+            region.dirty = true;
+        }
+    }
+}
+
+// Writes every loaded edited chunk, then rewrites only the regions that
+// changed, along with the player if there is one. Returns how many region
+// files were written.
+pub fn save_world(
+    save: &mut WorldSave,
+    world_map: &WorldMap,
+    chunk_index: &ChunkIndex,
+    player: Option<&PlayerState>,
+) -> io::Result<usize> {
+    for (&position, entry) in &chunk_index.chunks {
+        if let (true, Some(chunk)) = (entry.modified, world_map.chunks.get(&position)) {
+            save.store_chunk(position, chunk);
+        }
+    }
+
+    fs::create_dir_all(&save.dir)?;
+    if let Some(player) = player {
+        fs::write(save.dir.join(PLAYER_FILE), encode_player(player))?;
+    }
+    let mut written = 0;
+    for (&position, region) in save.regions.iter_mut().filter(|(_, region)| region.dirty) {
+        fs::write(region_path(&save.dir, position), write_region(region))?;
+        region.dirty = false;
+        written += 1;
+    }
+    Ok(written)
+}
+
+fn write_region(region: &Region) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN + region.chunks.len() * ENTRY_LEN);
+    let mut data = Vec::new();
+    header.extend_from_slice(REGION_MAGIC);
+    header.extend_from_slice(&REGION_VERSION.to_le_bytes());
+    header.extend_from_slice(&(region.chunks.len() as u32).to_le_bytes());
+
+    for (position, blob) in &region.chunks {
+        for value in position.to_array() {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(blob.len() as u32).to_le_bytes());
+        data.extend_from_slice(blob);
+    }
+
+    header.extend_from_slice(&data);
+    header
+}
+
+fn parse_region(bytes: &[u8]) -> Result<Region, SaveError> {
+    let read_u32 = |at: usize| -> Result<u32, SaveError> {
+        let word = bytes.get(at..at + 4).ok_or(SaveError::BadLength)?;
+        Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    };
+
+    if bytes.len() < HEADER_LEN {
+        return Err(SaveError::BadLength);
+    }
+    if &bytes[..REGION_MAGIC.len()] != REGION_MAGIC {
+        return Err(SaveError::BadMagic);
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != REGION_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+
+    let count = read_u32(6)? as usize;
+    let data_start = HEADER_LEN + count * ENTRY_LEN;
+    let mut region = Region::default();
+    for entry in 0..count {
+        let at = HEADER_LEN + entry * ENTRY_LEN;
+        let [x, y, z] = [at, at + 4, at + 8].map(|field| read_u32(field).map(|value| value as i32));
+        let offset = data_start + read_u32(at + 12)? as usize;
+        let len = read_u32(at + 16)? as usize;
+        let blob = bytes.get(offset..offset + len).ok_or(SaveError::BadLength)?;
+        region.chunks.insert(IVec3::new(x?, y?, z?), blob.to_vec());
+    }
+    Ok(region)
+}
+
+// Everything `save_world` needs to know about the player
+#[derive(SystemParam)]
+struct PlayerSource<'w, 's> {
+    world_origin: Res<'w, WorldOrigin>,
+    hotbar: Res<'w, Hotbar>,
+    player_query: Query<'w, 's, (&'static Transform, &'static MovementMode, &'static PlayerPhysics), With<Player>>,
+}
+
+impl PlayerSource<'_, '_> {
+    fn state(&self) -> Option<PlayerState> {
+        let (transform, &mode, physics) = self.player_query.get_single().ok()?;
+        Some(PlayerState {
+            feet: self.world_origin.to_world(transform.translation) - physics.up * EYE_HEIGHT,
+            rotation: transform.rotation,
+            mode,
+            hotbar_slots: self.hotbar.slots.to_vec(),
+            selected_slot: self.hotbar.selected,
+        })
+    }
+}
+
+fn save_and_report(save: &mut WorldSave, world_map: &WorldMap, chunk_index: &ChunkIndex, player: &PlayerSource) {
+    match save_world(save, world_map, chunk_index, player.state().as_ref()) {
+        Ok(written) => info!("Saved world to {} ({written} region files written)", save.dir.display()),
+        Err(err) => error!("Failed to save world to {}: {err}", save.dir.display()),
+    }
+}
+
+fn save_on_key(
+    input: Res<Input<KeyCode>>,
+    mut save: ResMut<WorldSave>,
+    world_map: Res<WorldMap>,
+    chunk_index: Res<ChunkIndex>,
+    player: PlayerSource,
+) {
+    if input.just_pressed(KeyCode::F5) {
+        save_and_report(&mut save, &world_map, &chunk_index, &player);
+    }
+}
+
+fn save_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    mut save: ResMut<WorldSave>,
+    world_map: Res<WorldMap>,
+    chunk_index: Res<ChunkIndex>,
+    player: PlayerSource,
+) {
+    if exit_events.read().next().is_some() {
+        save_and_report(&mut save, &world_map, &chunk_index, &player);
+    }
+}
+
+// Saved player state waiting for the player entity to exist
+#[derive(Resource)]
+struct RestoredPlayer(PlayerState);
+
+// Moves the spawn, and the world origin with it, to where the player was
+// saved, so the loading screen holds them until the terrain there is in
+fn load_player(
+    mut commands: Commands,
+    save: Res<WorldSave>,
+    mut settings: ResMut<Settings>,
+    mut world_origin: ResMut<WorldOrigin>,
+    mut hotbar: ResMut<Hotbar>,
+) {
+    let Some(state) = save.load_player() else {
+        return;
+    };
+    settings.spawn = Some(state.feet);
+    world_origin.chunk = world_to_chunk(state.feet.floor().as_ivec3()) * IVec3::new(1, 0, 1);
+    // A hotbar saved with a different number of slots keeps the defaults
+    if let Ok(slots) = state.hotbar_slots.as_slice().try_into() {
+        hotbar.slots = slots;
+    }
+    hotbar.selected = state.selected_slot.min(hotbar.slots.len() - 1);
+    commands.insert_resource(RestoredPlayer(state));
+}
+
+fn restore_player(
+    mut commands: Commands,
+    restored: Option<Res<RestoredPlayer>>,
+    mut player_query: Query<(&mut Transform, &mut MovementMode), With<Player>>,
+) {
+    let Some(restored) = restored else {
+        return;
+    };
+    for (mut transform, mut mode) in player_query.iter_mut() {
+        transform.rotation = restored.0.rotation;
+        *mode = restored.0.mode;
+    }
+    commands.remove_resource::<RestoredPlayer>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regions_round_trip_through_their_file_format() {
+        let mut region = Region::default();
+        region.chunks.insert(IVec3::new(-3, 1, 15), vec![1, 2, 3]);
+        region.chunks.insert(IVec3::new(0, -1, 0), Vec::new());
+        region.chunks.insert(IVec3::new(2, 0, -16), vec![9; 300]);
+        let parsed = parse_region(&write_region(&region)).unwrap();
+        assert_eq!(parsed.chunks, region.chunks);
+        assert!(!parsed.dirty);
+    }
+
+    #[test]
+    fn damaged_region_files_are_rejected() {
+        let mut region = Region::default();
+        region.chunks.insert(IVec3::ZERO, vec![7; 16]);
+        let bytes = write_region(&region);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[..4].copy_from_slice(b"VXCH");
+        assert_eq!(parse_region(&bad_magic).err(), Some(SaveError::BadMagic));
+        let mut future = bytes.clone();
+        future[4..6].copy_from_slice(&(REGION_VERSION + 1).to_le_bytes());
+        assert_eq!(parse_region(&future).err(), Some(SaveError::UnsupportedVersion(REGION_VERSION + 1)));
+        // Cut off inside the chunk's blob, then inside its header entry
+        assert_eq!(parse_region(&bytes[..bytes.len() - 1]).err(), Some(SaveError::BadLength));
+        assert_eq!(parse_region(&bytes[..HEADER_LEN + 4]).err(), Some(SaveError::BadLength));
+    }
+}
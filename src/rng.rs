@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+// Random numbers tied to a place in the world: the same seed and position
+// always give the same sequence, whichever order chunks generate in and on
+// whichever thread. Generation passes must take their randomness from here
+// rather than a shared RNG, whose output would depend on that order.
+// Draw several values from one RNG rather than reseeding per value.
+pub fn position_rng(seed: u32, position: IVec3) -> impl Rng {
+    let mut hash = mix(seed as u64);
+    for coordinate in position.to_array() {
+        hash = mix(hash ^ coordinate as u32 as u64);
+    }
+
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&hash.to_le_bytes());
+    bytes[8..].copy_from_slice(&mix(hash).to_le_bytes());
+    XorShiftRng::from_seed(bytes)
+}
+
+// SplitMix64 finalizer; neighboring inputs give unrelated outputs
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
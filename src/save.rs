@@ -0,0 +1,308 @@
+use std::fmt;
+
+use bevy::prelude::{Quat, Vec3};
+
+use crate::{BlockType, ChunkData, Facing, MovementMode, CHUNK_SIZE};
+
+// Every serialized chunk starts with this, then the format version
+const CHUNK_MAGIC: &[u8; 4] = b"VXCH";
+// Bump when the layout changes, and keep a decoder for each older version
+// so existing saves upgrade on load instead of being mis-parsed
+pub const FORMAT_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = CHUNK_MAGIC.len() + 2;
+const VOXEL_COUNT: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+// The player file follows the same scheme with its own magic and version
+const PLAYER_MAGIC: &[u8; 4] = b"VXPL";
+pub const PLAYER_FORMAT_VERSION: u16 = 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SaveError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    BadLength,
+    UnknownBlock(u8),
+    UnknownMovementMode(u8),
+    UnknownFacing(u8),
+    BadTransform,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::BadMagic => write!(f, "not a chunk save"),
+            SaveError::UnsupportedVersion(version) => {
+                write!(f, "unsupported chunk format version {version} (this build reads up to {FORMAT_VERSION})")
+            }
+            SaveError::BadLength => write!(f, "chunk data has the wrong length"),
+            SaveError::UnknownBlock(id) => write!(f, "unknown block id {id}"),
+            SaveError::UnknownMovementMode(id) => write!(f, "unknown movement mode id {id}"),
+            SaveError::UnknownFacing(id) => write!(f, "unknown block facing {id}"),
+            SaveError::BadTransform => write!(f, "player position or rotation is not finite"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+// Stable on-disk ids; never renumber existing blocks, only append
+fn block_to_bytes(block: BlockType) -> [u8; 2] {
+    match block {
+        BlockType::Air => [0, 0],
+        BlockType::Grass => [1, 0],
+        BlockType::Dirt => [2, 0],
+        BlockType::Stone => [3, 0],
+        BlockType::CoalOre => [4, 0],
+        BlockType::IronOre => [5, 0],
+        BlockType::Water => [6, 0],
+        BlockType::FlowingWater(level) => [7, level],
+        BlockType::Bedrock => [8, 0],
+        BlockType::Glowstone => [9, 0],
+        BlockType::Leaves => [10, 0],
+        BlockType::Sand => [11, 0],
+        BlockType::Log(facing) => [12, facing.index()],
+        BlockType::StoneSlab => [13, 0],
+        BlockType::Glass => [14, 0],
+    }
+}
+
+fn block_from_bytes([id, data]: [u8; 2]) -> Result<BlockType, SaveError> {
+    Ok(match id {
+        0 => BlockType::Air,
+        1 => BlockType::Grass,
+        2 => BlockType::Dirt,
+        3 => BlockType::Stone,
+        4 => BlockType::CoalOre,
+        5 => BlockType::IronOre,
+        6 => BlockType::Water,
+        7 => BlockType::FlowingWater(data),
+        8 => BlockType::Bedrock,
+        9 => BlockType::Glowstone,
+        10 => BlockType::Leaves,
+        11 => BlockType::Sand,
+        12 => BlockType::Log(Facing::from_index(data).ok_or(SaveError::UnknownFacing(data))?),
+        13 => BlockType::StoneSlab,
+        14 => BlockType::Glass,
+        _ => return Err(SaveError::UnknownBlock(id)),
+    })
+}
+
+// Voxels only; light is recomputed once the chunk is back in the world
+pub fn encode_chunk(chunk: &ChunkData) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + VOXEL_COUNT * 2);
+    bytes.extend_from_slice(CHUNK_MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    for &block in &chunk.voxels {
+        bytes.extend_from_slice(&block_to_bytes(block));
+    }
+    bytes
+}
+
+pub fn decode_chunk(bytes: &[u8]) -> Result<ChunkData, SaveError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(SaveError::BadLength);
+    }
+    let (header, body) = bytes.split_at(HEADER_LEN);
+    if &header[..CHUNK_MAGIC.len()] != CHUNK_MAGIC {
+        return Err(SaveError::BadMagic);
+    }
+
+    match u16::from_le_bytes([header[4], header[5]]) {
+        1 => decode_v1(body),
+        version => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+// Two bytes per voxel, block id then block data, in storage order
+fn decode_v1(body: &[u8]) -> Result<ChunkData, SaveError> {
+    if body.len() != VOXEL_COUNT * 2 {
+        return Err(SaveError::BadLength);
+    }
+    let mut chunk = ChunkData::new();
+    for (voxel, pair) in chunk.voxels.iter_mut().zip(body.chunks_exact(2)) {
+        *voxel = block_from_bytes([pair[0], pair[1]])?;
+    }
+    chunk.density_from_voxels();
+    Ok(chunk)
+}
+
+// Where the player left off, restored when the world is loaded again. The
+// game has no health yet, so there is none to save.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerState {
+    // Absolute position of the player's feet, in blocks
+    pub feet: Vec3,
+    pub rotation: Quat,
+    pub mode: MovementMode,
+    pub hotbar_slots: Vec<BlockType>,
+    pub selected_slot: usize,
+}
+
+fn mode_to_byte(mode: MovementMode) -> u8 {
+    match mode {
+        MovementMode::Walk => 0,
+        MovementMode::Fly => 1,
+        MovementMode::FlyCollide => 2,
+    }
+}
+
+fn mode_from_byte(id: u8) -> Result<MovementMode, SaveError> {
+    Ok(match id {
+        0 => MovementMode::Walk,
+        1 => MovementMode::Fly,
+        2 => MovementMode::FlyCollide,
+        _ => return Err(SaveError::UnknownMovementMode(id)),
+    })
+}
+
+pub fn encode_player(state: &PlayerState) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(PLAYER_MAGIC);
+    bytes.extend_from_slice(&PLAYER_FORMAT_VERSION.to_le_bytes());
+    for value in state.feet.to_array().into_iter().chain(state.rotation.to_array()) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.push(mode_to_byte(state.mode));
+    bytes.push(state.selected_slot as u8);
+    bytes.push(state.hotbar_slots.len() as u8);
+    for &block in &state.hotbar_slots {
+        bytes.extend_from_slice(&block_to_bytes(block));
+    }
+    bytes
+}
+
+pub fn decode_player(bytes: &[u8]) -> Result<PlayerState, SaveError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(SaveError::BadLength);
+    }
+    let (header, body) = bytes.split_at(HEADER_LEN);
+    if &header[..PLAYER_MAGIC.len()] != PLAYER_MAGIC {
+        return Err(SaveError::BadMagic);
+    }
+
+    match u16::from_le_bytes([header[4], header[5]]) {
+        1 => decode_player_v1(body),
+        version => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+// Feet then rotation as little-endian f32s, then the movement mode, selected
+// slot and slot count, then two bytes per hotbar slot
+fn decode_player_v1(body: &[u8]) -> Result<PlayerState, SaveError> {
+    const FLOATS_LEN: usize = 7 * 4;
+    if body.len() < FLOATS_LEN + 3 {
+        return Err(SaveError::BadLength);
+    }
+    let (floats, rest) = body.split_at(FLOATS_LEN);
+    let floats: Vec<f32> =
+        floats.chunks_exact(4).map(|word| f32::from_le_bytes([word[0], word[1], word[2], word[3]])).collect();
+    let (mode, selected_slot, slot_count) = (rest[0], rest[1] as usize, rest[2] as usize);
+    let slots = &rest[3..];
+    if slots.len() != slot_count * 2 {
+        return Err(SaveError::BadLength);
+    }
+
+    // A zero rotation would normalize to NaN and leave the camera unusable
+    let (feet, rotation) = (Vec3::from_slice(&floats[..3]), Quat::from_slice(&floats[3..]));
+    if !feet.is_finite() || !rotation.is_finite() || rotation.length_squared() < f32::EPSILON {
+        return Err(SaveError::BadTransform);
+    }
+
+    Ok(PlayerState {
+        feet,
+        rotation: rotation.normalize(),
+        mode: mode_from_byte(mode)?,
+        hotbar_slots: slots.chunks_exact(2).map(|pair| block_from_bytes([pair[0], pair[1]])).collect::<Result<_, _>>()?,
+        selected_slot,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::IVec3;
+
+    use super::*;
+
+    fn sample_chunk() -> ChunkData {
+        let mut chunk = ChunkData::new();
+        chunk.set(IVec3::new(0, 0, 0), BlockType::Bedrock);
+        chunk.set(IVec3::new(3, 4, 5), BlockType::FlowingWater(3));
+        chunk.set(IVec3::new(15, 15, 15), BlockType::Log(Facing::PosX));
+        chunk.set(IVec3::new(7, 1, 9), BlockType::Glass);
+        chunk
+    }
+
+    fn sample_player() -> PlayerState {
+        PlayerState {
+            feet: Vec3::new(-12.5, 40.0, 3.25),
+            rotation: Quat::from_rotation_y(1.0),
+            mode: MovementMode::FlyCollide,
+            hotbar_slots: vec![BlockType::Stone, BlockType::Log(Facing::Up), BlockType::StoneSlab],
+            selected_slot: 2,
+        }
+    }
+
+    // The header's version field, for forging saves from other versions
+    fn with_version(mut bytes: Vec<u8>, version: u16) -> Vec<u8> {
+        bytes[4..6].copy_from_slice(&version.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn chunks_round_trip_through_a_v1_save() {
+        let chunk = sample_chunk();
+        let bytes = encode_chunk(&chunk);
+        assert_eq!(&bytes[..4], CHUNK_MAGIC);
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION);
+        assert_eq!(decode_chunk(&bytes).unwrap().voxels, chunk.voxels);
+    }
+
+    #[test]
+    fn players_round_trip_through_a_v1_save() {
+        let state = sample_player();
+        let decoded = decode_player(&encode_player(&state)).unwrap();
+        assert_eq!(decoded.feet, state.feet);
+        assert!(decoded.rotation.abs_diff_eq(state.rotation, 1e-6));
+        assert_eq!((decoded.mode, decoded.selected_slot), (state.mode, state.selected_slot));
+        assert_eq!(decoded.hotbar_slots, state.hotbar_slots);
+    }
+
+    #[test]
+    fn saves_from_unknown_versions_are_rejected() {
+        let chunk = with_version(encode_chunk(&sample_chunk()), FORMAT_VERSION + 1);
+        assert_eq!(decode_chunk(&chunk).err(), Some(SaveError::UnsupportedVersion(FORMAT_VERSION + 1)));
+        let player = with_version(encode_player(&sample_player()), 0);
+        assert_eq!(decode_player(&player).err(), Some(SaveError::UnsupportedVersion(0)));
+    }
+
+    #[test]
+    fn saves_with_the_wrong_magic_are_rejected() {
+        let mut chunk = encode_chunk(&sample_chunk());
+        chunk[0] = b'X';
+        assert_eq!(decode_chunk(&chunk).err(), Some(SaveError::BadMagic));
+        // A chunk save is not a player save, and the other way around
+        assert_eq!(decode_player(&encode_chunk(&sample_chunk())).err(), Some(SaveError::BadMagic));
+        assert_eq!(decode_chunk(&encode_player(&sample_player())).err(), Some(SaveError::BadMagic));
+    }
+
+    #[test]
+    fn players_with_a_broken_transform_are_rejected() {
+        let zeroed = PlayerState { rotation: Quat::from_xyzw(0.0, 0.0, 0.0, 0.0), ..sample_player() };
+        assert_eq!(decode_player(&encode_player(&zeroed)).err(), Some(SaveError::BadTransform));
+        let lost = PlayerState { feet: Vec3::new(f32::NAN, 40.0, 0.0), ..sample_player() };
+        assert_eq!(decode_player(&encode_player(&lost)).err(), Some(SaveError::BadTransform));
+        let endless = PlayerState { rotation: Quat::from_xyzw(f32::INFINITY, 0.0, 0.0, 1.0), ..sample_player() };
+        assert_eq!(decode_player(&encode_player(&endless)).err(), Some(SaveError::BadTransform));
+    }
+
+    #[test]
+    fn truncated_saves_are_rejected() {
+        let chunk = encode_chunk(&sample_chunk());
+        assert_eq!(decode_chunk(&chunk[..chunk.len() - 1]).err(), Some(SaveError::BadLength));
+        assert_eq!(decode_chunk(&chunk[..3]).err(), Some(SaveError::BadLength));
+        let player = encode_player(&sample_player());
+        assert_eq!(decode_player(&player[..player.len() - 1]).err(), Some(SaveError::BadLength));
+        assert_eq!(decode_player(&player[..HEADER_LEN + 10]).err(), Some(SaveError::BadLength));
+    }
+}
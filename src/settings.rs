@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::window::PresentMode;
+use serde::{Deserialize, Serialize};
+
+use crate::sky::{default_sky_ramp, SkyKeyframe};
+use crate::{Biome, MovementMode, NoiseKind, CHUNK_SIZE};
+
+#[derive(Resource)]
+pub struct Settings {
+    // Chunks loaded in each horizontal direction around the player
+    pub render_distance: i32,
+    // Radius loaded before the player gets control, so play starts sooner;
+    // it then grows to `render_distance` over `render_distance_ramp` seconds
+    // while the rest of the world streams in
+    pub initial_render_distance: i32,
+    pub render_distance_ramp: f32,
+    pub near: f32,
+    // Overrides the far plane, which otherwise follows the render distance
+    pub far: Option<f32>,
+    pub face_shading: FaceShading,
+    pub biome_tints: BiomeTints,
+    pub mesh_style: MeshStyle,
+    pub mesh_topology: MeshTopology,
+    // Vertical field of view in degrees
+    pub fov: f32,
+    // Extra degrees of FOV while sprinting, eased in and out at `fov_kick_speed`
+    pub sprint_fov_kick: f32,
+    pub fov_kick_speed: f32,
+    // Radians of rotation per unit of mouse movement
+    pub mouse_sensitivity: f32,
+    // Flip look direction per axis; inverted Y pitches down when the mouse moves up
+    pub invert_x: bool,
+    pub invert_y: bool,
+    // Raw input reads device deltas, which skip OS pointer acceleration and
+    // feel the same on every machine. Turning it off follows the on-screen
+    // pointer instead, so look speed matches the desktop acceleration curve.
+    pub raw_mouse_input: bool,
+    // Chunk columns generated in the background at once; the rest wait their turn
+    pub max_concurrent_chunk_tasks: usize,
+    // Main-thread time per frame for bringing chunks in (meshing generated
+    // columns, loading saved chunks); whatever doesn't fit waits a frame, so
+    // fast machines stream faster without slow ones stuttering. The loading
+    // screen has nothing else to draw, so it gets more.
+    pub chunk_time_budget: Duration,
+    pub loading_chunk_time_budget: Duration,
+    // How long a chunk stays loaded once out of range, so one the player
+    // soon comes back to is still there instead of generating again
+    pub chunk_despawn_delay: Duration,
+    // Render units per voxel; the player and its speeds keep their size, and
+    // so do the hills, which regenerate with more or fewer blocks
+    pub voxel_scale: f32,
+    // Chunks further than this from the player, counted like the render
+    // distance, are meshed at half resolution
+    pub lod_distance: i32,
+    // Seconds blocky chunks take to morph between detail levels instead of
+    // popping; zero switches them at once
+    pub lod_morph_duration: f32,
+    // Absolute position in blocks the player's feet start at; `None` stands
+    // them on the surface above the world origin
+    pub spawn: Option<Vec3>,
+    // A walking player this many blocks below the bottom of the world has
+    // fallen out of it, and is put back where `void_rescue` says
+    pub void_margin: f32,
+    pub void_rescue: VoidRescue,
+    // Real seconds in a full day; zero stops the clock
+    pub day_length: f32,
+    // Sky, sun and ambient light over the day, blended by `TimeOfDay`
+    pub sky_ramp: Vec<SkyKeyframe>,
+    // How frames reach the screen: `Fifo` waits for vsync, `Immediate` tears
+    // but never waits, `Mailbox` skips waiting without tearing where supported
+    pub present_mode: PresentMode,
+    // Frames per second to stop at, on top of any vsync; `None` runs uncapped
+    pub max_fps: Option<f32>,
+    // MSAA samples per pixel: 1 (off), 2, 4 or 8; others round to the nearest.
+    // Smooths block edges, but fill and memory cost grow with every sample, so
+    // low-end GPUs may want it off at high resolutions.
+    pub msaa_samples: u32,
+    // How the block atlas is sampled, and how many samples anisotropic
+    // filtering takes for distant, glancing faces (1 is off). Anisotropy only
+    // applies to `Linear`.
+    pub atlas_filter: TextureFilter,
+    pub atlas_anisotropy: u16,
+    // Distance, in render units, blocks can be broken, placed and picked at
+    pub reach: f32,
+    // Reach while flying, when building from afar is the point
+    pub fly_reach: f32,
+    // Sways the camera up and down while walking, scaled by speed; the aim
+    // and collisions keep the steady eye
+    pub head_bob: bool,
+    // Peak offset in render units, and full sways per block walked
+    pub head_bob_amplitude: f32,
+    pub head_bob_frequency: f32,
+    // How far behind the player the third-person camera sits, in render
+    // units; it comes closer wherever a block is in the way
+    pub third_person_distance: f32,
+    // Noise behind the terrain and climate; changing it regenerates every
+    // loaded chunk that hasn't been edited
+    pub noise: NoiseKind,
+    // Inclusive world Y bounds in blocks: bedrock lies at `world_bottom`, and
+    // nothing generates or can be built outside them. Changing them
+    // regenerates like `noise` does.
+    pub world_bottom: i32,
+    pub world_top: i32,
+}
+
+// How solid terrain is meshed. `Smooth` draws a surface through the chunk
+// density field instead of cubes; collision stays per voxel either way.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum MeshStyle {
+    #[default]
+    Blocky,
+    Smooth,
+}
+
+// How chunk meshes hand their quads to the GPU. Strips join every quad into
+// one strip through degenerate triangles; they are here for benchmarking
+// against lists, which stay the default.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MeshTopology {
+    #[default]
+    TriangleList,
+    TriangleStrip,
+}
+
+// How block textures are filtered
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TextureFilter {
+    // Crisp texels, as the pixel art was drawn
+    #[default]
+    Nearest,
+    // Smooth, especially in the distance, and able to filter anisotropically
+    Linear,
+}
+
+// Where a player who fell out of the world is put back
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum VoidRescue {
+    // On the highest solid block above where they fell
+    #[default]
+    Surface,
+    // At the spawn point
+    Spawn,
+}
+
+// Brightness multipliers baked into vertex colors by face orientation, so
+// blocks stay readable without relying on scene lighting
+#[derive(Clone, Copy)]
+pub struct FaceShading {
+    pub top: f32,
+    pub side_x: f32,
+    pub side_z: f32,
+    pub bottom: f32,
+}
+
+impl Default for FaceShading {
+    fn default() -> Self {
+        Self {
+            top: 1.0,
+            side_x: 0.6,
+            side_z: 0.8,
+            bottom: 0.5,
+        }
+    }
+}
+
+impl FaceShading {
+    pub fn brightness(&self, normal: [f32; 3]) -> f32 {
+        match normal {
+            [_, y, _] if y > 0.0 => self.top,
+            [_, y, _] if y < 0.0 => self.bottom,
+            [x, _, _] if x != 0.0 => self.side_x,
+            _ => self.side_z,
+        }
+    }
+}
+
+// Multiplied into the colors of natural terrain (dirt, grass sides, sand,
+// stone and leaves) by biome, blending across borders like the grass tint,
+// so regions read apart until blocks get textures
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BiomeTints {
+    pub desert: Color,
+    pub savanna: Color,
+    pub plains: Color,
+    pub forest: Color,
+    pub taiga: Color,
+    pub tundra: Color,
+}
+
+impl Default for BiomeTints {
+    fn default() -> Self {
+        Self {
+            desert: Color::rgb(1.15, 1.05, 0.8),
+            savanna: Color::rgb(1.15, 0.95, 0.75),
+            plains: Color::rgb(1.1, 0.95, 0.8),
+            forest: Color::rgb(0.9, 0.8, 0.7),
+            taiga: Color::rgb(0.85, 0.85, 0.9),
+            tundra: Color::rgb(1.05, 1.1, 1.2),
+        }
+    }
+}
+
+impl BiomeTints {
+    pub(crate) fn get(&self, biome: Biome) -> Color {
+        match biome {
+            Biome::Desert => self.desert,
+            Biome::Savanna => self.savanna,
+            Biome::Plains => self.plains,
+            Biome::Forest => self.forest,
+            Biome::Taiga => self.taiga,
+            Biome::Tundra => self.tundra,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            render_distance: 4,
+            initial_render_distance: 2,
+            render_distance_ramp: 3.0,
+            near: 0.05,
+            far: None,
+            face_shading: FaceShading::default(),
+            biome_tints: BiomeTints::default(),
+            mesh_style: MeshStyle::default(),
+            mesh_topology: MeshTopology::default(),
+            fov: 70.0,
+            sprint_fov_kick: 8.0,
+            fov_kick_speed: 8.0,
+            mouse_sensitivity: 0.002,
+            invert_x: false,
+            invert_y: false,
+            raw_mouse_input: true,
+            max_concurrent_chunk_tasks: 4,
+            chunk_time_budget: Duration::from_millis(4),
+            loading_chunk_time_budget: Duration::from_millis(20),
+            chunk_despawn_delay: Duration::from_secs(2),
+            voxel_scale: 1.0,
+            lod_distance: 8,
+            lod_morph_duration: 0.4,
+            spawn: None,
+            void_margin: 16.0,
+            void_rescue: VoidRescue::default(),
+            day_length: 600.0,
+            sky_ramp: default_sky_ramp(),
+            present_mode: PresentMode::AutoVsync,
+            max_fps: None,
+            msaa_samples: 4,
+            atlas_filter: TextureFilter::default(),
+            atlas_anisotropy: 1,
+            reach: 6.0,
+            fly_reach: 12.0,
+            head_bob: false,
+            head_bob_amplitude: 0.05,
+            head_bob_frequency: 0.5,
+            third_person_distance: 4.0,
+            noise: NoiseKind::default(),
+            world_bottom: 0,
+            world_top: 2 * CHUNK_SIZE - 1,
+        }
+    }
+}
+
+impl Settings {
+    pub fn reach(&self, mode: MovementMode) -> f32 {
+        match mode {
+            MovementMode::Walk => self.reach,
+            MovementMode::Fly | MovementMode::FlyCollide => self.fly_reach,
+        }
+    }
+
+    // Never beyond the full render distance
+    pub fn initial_render_distance(&self) -> i32 {
+        self.initial_render_distance.clamp(1, self.render_distance.max(1))
+    }
+
+    // The radius to load `seconds` after the player got control
+    pub fn ramped_render_distance(&self, seconds: f32) -> i32 {
+        let initial = self.initial_render_distance();
+        let progress = (seconds / self.render_distance_ramp.max(f32::EPSILON)).min(1.0);
+        initial + ((self.render_distance - initial) as f32 * progress).round() as i32
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        match self.msaa_samples {
+            0 | 1 => Msaa::Off,
+            2 => Msaa::Sample2,
+            3..=5 => Msaa::Sample4,
+            _ => Msaa::Sample8,
+        }
+    }
+
+    // Far enough to reach the corners of the furthest loaded chunk column
+    pub fn far_plane(&self, world_height: f32) -> f32 {
+        self.far.unwrap_or_else(|| {
+            let horizontal = ((self.render_distance + 1) * CHUNK_SIZE) as f32 * std::f32::consts::SQRT_2;
+            Vec2::new(horizontal, world_height).length() * self.voxel_scale
+        })
+    }
+}
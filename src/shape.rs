@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+// An axis-aligned box inside a block, in blocks from the block's low corner
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockAabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BlockAabb {
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    // Whether this box, placed with the block's low corner at `corner` and
+    // grown by `scale`, overlaps the box from `min` to `max`. Touching faces
+    // don't count, so standing on a box isn't colliding with it.
+    pub fn overlaps(&self, corner: Vec3, scale: f32, min: Vec3, max: Vec3) -> bool {
+        let (low, high) = (corner + self.min * scale, corner + self.max * scale);
+        min.cmplt(high).all() && max.cmpgt(low).all()
+    }
+
+    // How far along the ray from `origin` in the direction `dir` it enters
+    // this box, placed with the block's low corner at `corner`, and on which
+    // axis. Zero distance and no axis if the ray starts inside it.
+    pub fn ray_entry(&self, corner: Vec3, origin: Vec3, dir: Vec3) -> Option<(f32, Option<usize>)> {
+        let (low, high) = (corner + self.min - origin, corner + self.max - origin);
+        let (mut near, mut far, mut axis) = (0.0, f32::INFINITY, None);
+        for i in 0..3 {
+            if dir[i] == 0.0 {
+                if low[i] > 0.0 || high[i] < 0.0 {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = (low[i] / dir[i], high[i] / dir[i]);
+            if a.min(b) > near {
+                near = a.min(b);
+                axis = Some(i);
+            }
+            far = far.min(a.max(b));
+        }
+        (near <= far).then_some((near, axis))
+    }
+}
+
+// What a block collides as, as a list of boxes; see `BlockType::collision_shape`
+pub const EMPTY_SHAPE: &[BlockAabb] = &[];
+pub const FULL_SHAPE: &[BlockAabb] = &[BlockAabb::new(Vec3::ZERO, Vec3::ONE)];
+pub const LOWER_SLAB_SHAPE: &[BlockAabb] = &[BlockAabb::new(Vec3::ZERO, Vec3::new(1.0, 0.5, 1.0))];
@@ -0,0 +1,146 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::GameState;
+
+// Sunlight at full strength, scaled down by the keyframes' sun color
+const SUN_ILLUMINANCE: f32 = 10_000.0;
+// Time of day the world starts at, shortly after sunrise
+const START_TIME: f32 = 0.3;
+
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TimeOfDay(START_TIME))
+            .init_resource::<ClearColor>()
+            .init_resource::<AmbientLight>()
+            .add_systems(Startup, spawn_sun)
+            .add_systems(Update, (advance_time.run_if(in_state(GameState::Playing)), update_sky).chain());
+    }
+}
+
+// How far through the day it is, from 0 at midnight through 0.5 at noon
+// back round to 1. The sun rises at 0.25 and sets at 0.75.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct TimeOfDay(pub f32);
+
+// The look of the sky at one time of day; `Settings::sky_ramp` blends
+// between these
+#[derive(Clone, Copy, Debug)]
+pub struct SkyKeyframe {
+    // Same scale as `TimeOfDay`
+    pub time: f32,
+    pub sky: Color,
+    // Black once the sun is down
+    pub sun: Color,
+    pub ambient: f32,
+}
+
+// Night, dawn, noon, dusk and back to night
+pub fn default_sky_ramp() -> Vec<SkyKeyframe> {
+    let night = SkyKeyframe {
+        time: 0.0,
+        sky: Color::rgb(0.02, 0.03, 0.08),
+        sun: Color::BLACK,
+        ambient: 0.03,
+    };
+    vec![
+        SkyKeyframe { time: 0.18, ..night },
+        SkyKeyframe {
+            time: 0.25,
+            sky: Color::rgb(0.85, 0.5, 0.35),
+            sun: Color::rgb(1.0, 0.6, 0.4),
+            ambient: 0.15,
+        },
+        SkyKeyframe {
+            time: 0.5,
+            sky: Color::rgb(0.5, 0.7, 1.0),
+            sun: Color::rgb(1.0, 0.97, 0.9),
+            ambient: 0.35,
+        },
+        SkyKeyframe {
+            time: 0.75,
+            sky: Color::rgb(0.9, 0.45, 0.3),
+            sun: Color::rgb(1.0, 0.5, 0.3),
+            ambient: 0.15,
+        },
+        SkyKeyframe { time: 0.82, ..night },
+    ]
+}
+
+// Blends the two keyframes around `time`, wrapping from the last one of the
+// day to the first. `None` if there are no keyframes.
+pub fn sample_sky(ramp: &[SkyKeyframe], time: f32) -> Option<SkyKeyframe> {
+    let mut sorted = ramp.to_vec();
+    sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+    let time = time.rem_euclid(1.0);
+    let next_index = sorted.iter().position(|keyframe| keyframe.time > time).unwrap_or(0);
+    let next = *sorted.get(next_index)?;
+    let previous = sorted[(next_index + sorted.len() - 1) % sorted.len()];
+
+    let span = (next.time - previous.time).rem_euclid(1.0);
+    let t = if span > 0.0 { (time - previous.time).rem_euclid(1.0) / span } else { 0.0 };
+    Some(SkyKeyframe {
+        time,
+        sky: lerp_color(previous.sky, next.sky, t),
+        sun: lerp_color(previous.sun, next.sun, t),
+        ambient: previous.ambient + (next.ambient - previous.ambient) * t,
+    })
+}
+
+// In linear space, so halfway between two colors isn't too dark
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_linear_rgba_f32());
+    let to = Vec4::from(to.as_linear_rgba_f32());
+    let [r, g, b, a] = from.lerp(to, t).to_array();
+    Color::rgba_linear(r, g, b, a)
+}
+
+#[derive(Component)]
+struct Sun;
+
+fn spawn_sun(mut commands: Commands) {
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                illuminance: SUN_ILLUMINANCE,
+                ..default()
+            },
+            ..default()
+        },
+        Sun,
+    ));
+}
+
+fn advance_time(time: Res<Time>, settings: Res<Settings>, mut time_of_day: ResMut<TimeOfDay>) {
+    if settings.day_length > 0.0 {
+        time_of_day.0 = (time_of_day.0 + time.delta_seconds() / settings.day_length).rem_euclid(1.0);
+    }
+}
+
+fn update_sky(
+    settings: Res<Settings>,
+    time_of_day: Res<TimeOfDay>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun_query: Query<(&mut DirectionalLight, &mut Transform), With<Sun>>,
+) {
+    let Some(keyframe) = sample_sky(&settings.sky_ramp, time_of_day.0) else {
+        return;
+    };
+    clear_color.0 = keyframe.sky;
+    ambient.color = keyframe.sky;
+    ambient.brightness = keyframe.ambient;
+
+    // Rises in the east, peaks at noon and sets in the west, tilted a little
+    // south so it's never straight overhead
+    let angle = (time_of_day.0 - 0.25) * TAU;
+    let towards_sun = Vec3::new(angle.cos(), angle.sin(), 0.3).normalize();
+    for (mut light, mut transform) in sun_query.iter_mut() {
+        light.color = keyframe.sun;
+        *transform = Transform::IDENTITY.looking_to(-towards_sun, Vec3::Y);
+    }
+}
@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+
+use crate::{BlockType, ChunkMeshBuilder, Facing, CHUNK_SIZE};
+
+// Depth, in samples, of the curtain hung below the border of a coarse mesh
+const SKIRT_DEPTH: f32 = 1.0;
+
+// The other two axes of each axis, ordered so quads built from them face
+// along the positive axis
+const AXES: [(usize, usize, usize); 3] = [(0, 1, 2), (1, 2, 0), (2, 0, 1)];
+
+// Sample offsets of the corners of a cell, and the corner pairs of its edges
+const CORNERS: [IVec3; 8] = [
+    IVec3::new(0, 0, 0),
+    IVec3::new(1, 0, 0),
+    IVec3::new(0, 1, 0),
+    IVec3::new(1, 1, 0),
+    IVec3::new(0, 0, 1),
+    IVec3::new(1, 0, 1),
+    IVec3::new(0, 1, 1),
+    IVec3::new(1, 1, 1),
+];
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+// Samples per axis at a given step: one per step voxels across the chunk,
+// plus one on each side so cells along the border can still place their
+// vertex. Each cell spans two neighboring samples.
+struct Grid {
+    step: i32,
+    // Samples inside the chunk along each axis
+    size: i32,
+}
+
+impl Grid {
+    fn samples(&self) -> i32 {
+        self.size + 2
+    }
+
+    fn cells(&self) -> i32 {
+        self.size + 1
+    }
+
+    fn sample_index(&self, sample: IVec3) -> usize {
+        let p = sample + IVec3::ONE;
+        (p.x + self.samples() * (p.y + self.samples() * p.z)) as usize
+    }
+
+    fn cell_index(&self, cell: IVec3) -> usize {
+        let c = cell + IVec3::ONE;
+        (c.x + self.cells() * (c.y + self.cells() * c.z)) as usize
+    }
+
+    // Chunk-local voxel a sample is taken at
+    fn voxel(&self, sample: IVec3) -> IVec3 {
+        sample * self.step
+    }
+}
+
+// Naive surface nets over the density sampled at voxel centers. Every cell
+// the surface passes through gets one vertex, at the mean of its edge
+// crossings, and each crossed sample edge becomes a quad joining the four
+// cells around it. A chunk only emits the edges starting inside it, so
+// neighboring chunks meet without gaps or overlap.
+//
+// Density is sampled every `step` voxels; above one the surface is coarser
+// than its neighbors', so a skirt hangs off its border to hide the cracks.
+//
+// `density`, `block` and `light` take chunk-local voxel positions, which may
+// lie up to `step` voxels outside the chunk; `color` gives a solid block's color.
+pub fn add_smooth_surface(
+    builder: &mut ChunkMeshBuilder,
+    step: i32,
+    density: impl Fn(IVec3) -> f32,
+    block: impl Fn(IVec3) -> BlockType,
+    color: impl Fn(IVec3, BlockType) -> Color,
+    light: impl Fn(IVec3) -> f32,
+) {
+    let grid = Grid { step, size: CHUNK_SIZE / step };
+    let size = grid.size;
+    let samples: Vec<f32> = (-1..=size)
+        .flat_map(|z| (-1..=size).flat_map(move |y| (-1..=size).map(move |x| IVec3::new(x, y, z))))
+        .map(|sample| density(grid.voxel(sample)))
+        .collect();
+    let sample = |at: IVec3| samples[grid.sample_index(at)];
+
+    let mut vertices = vec![None; grid.cells().pow(3) as usize];
+    for z in -1..size {
+        for y in -1..size {
+            for x in -1..size {
+                let cell = IVec3::new(x, y, z);
+                vertices[grid.cell_index(cell)] = cell_vertex(cell, &sample);
+            }
+        }
+    }
+    let vertex = |cell: IVec3| vertices[grid.cell_index(cell)].map(|vertex| grid_to_local(vertex, step));
+
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                let start = IVec3::new(x, y, z);
+                for (axis, u, v) in AXES {
+                    let mut edge = IVec3::ZERO;
+                    edge[axis] = 1;
+                    let end = start + edge;
+                    let (inside, outside) = match (sample(start) > 0.0, sample(end) > 0.0) {
+                        (true, false) => (start, end),
+                        (false, true) => (end, start),
+                        _ => continue,
+                    };
+
+                    let mut du = IVec3::ZERO;
+                    du[u] = 1;
+                    let mut dv = IVec3::ZERO;
+                    dv[v] = 1;
+                    let cells = [start - du - dv, start - dv, start, start - du];
+                    let Some(mut corners) = cells.iter().map(|&cell| vertex(cell)).collect::<Option<Vec<Vec3>>>()
+                    else {
+                        continue;
+                    };
+                    // Quads face away from the solid side
+                    if inside == end {
+                        corners.reverse();
+                    }
+
+                    let normal = (corners[1] - corners[0]).cross(corners[3] - corners[0]).normalize_or_zero();
+                    let (inside, outside) = (grid.voxel(inside), grid.voxel(outside));
+                    let solid = block(inside);
+                    builder.add_face(
+                        [corners[0], corners[1], corners[2], corners[3]].map(|corner| corner.to_array()),
+                        normal.to_array(),
+                        color(inside, solid),
+                        light(outside),
+                        solid.face_texture(Facing::nearest(normal)),
+                    );
+                }
+            }
+        }
+    }
+
+    if step > 1 {
+        add_skirt(builder, &grid, &sample, &vertex, &block, &color, &light);
+    }
+}
+
+// Hangs a curtain below the surface along each side of the chunk, joining the
+// vertices of neighboring border cells. A finer neighbor places its border
+// vertices a little differently, and the curtain fills the gap between them
+// when seen from the side. Both windings are added so it shows from either side.
+fn add_skirt(
+    builder: &mut ChunkMeshBuilder,
+    grid: &Grid,
+    sample: &impl Fn(IVec3) -> f32,
+    vertex: &impl Fn(IVec3) -> Option<Vec3>,
+    block: &impl Fn(IVec3) -> BlockType,
+    color: &impl Fn(IVec3, BlockType) -> Color,
+    light: &impl Fn(IVec3) -> f32,
+) {
+    let depth = Vec3::Y * SKIRT_DEPTH * grid.step as f32;
+    // Border cells lie at either end of the x and z axes, and run along the other one
+    for (axis, along) in [(0, 2), (2, 0)] {
+        for border in [-1, grid.size - 1] {
+            for y in -1..grid.size {
+                for a in -1..grid.size - 1 {
+                    let mut cell = IVec3::ZERO;
+                    cell[axis] = border;
+                    cell[along] = a;
+                    cell.y = y;
+                    let mut next = cell;
+                    next[along] += 1;
+                    let (Some(start), Some(end)) = (vertex(cell), vertex(next)) else {
+                        continue;
+                    };
+                    // Colored by the solid corner of the cell and lit from open air
+                    let corners = CORNERS.map(|corner| cell + corner);
+                    let (Some(&solid), Some(&open)) = (
+                        corners.iter().find(|&&at| sample(at) > 0.0),
+                        corners.iter().find(|&&at| sample(at) <= 0.0),
+                    ) else {
+                        continue;
+                    };
+                    let (solid, open) = (grid.voxel(solid), grid.voxel(open));
+                    let (color, light) = (color(solid, block(solid)), light(open));
+
+                    // The side each winding faces depends on which way the border runs
+                    let normal = (end - start).cross(-depth).normalize_or_zero();
+                    let texture = block(solid).face_texture(Facing::nearest(normal));
+                    let quad = [start, end, end - depth, start - depth];
+                    let reversed = [start - depth, end - depth, end, start];
+                    builder.add_face(quad.map(|corner| corner.to_array()), normal.to_array(), color, light, texture);
+                    let reversed_normal = (-normal).to_array();
+                    builder.add_face(reversed.map(|corner| corner.to_array()), reversed_normal, color, light, texture);
+                }
+            }
+        }
+    }
+}
+
+// Chunk-local block position of a point on the sample grid
+fn grid_to_local(point: Vec3, step: i32) -> Vec3 {
+    // Samples sit at voxel centers, half a block in from the voxel corner
+    point * step as f32 + Vec3::splat(0.5)
+}
+
+// Mean of the points where the surface crosses the cell's edges, on the
+// sample grid, or `None` if the cell is entirely on one side
+fn cell_vertex(cell: IVec3, sample: &impl Fn(IVec3) -> f32) -> Option<Vec3> {
+    let values = CORNERS.map(|corner| sample(cell + corner));
+    let mut sum = Vec3::ZERO;
+    let mut crossings = 0.0;
+    for (a, b) in EDGES {
+        let (da, db) = (values[a], values[b]);
+        if (da > 0.0) != (db > 0.0) {
+            let t = da / (da - db);
+            sum += CORNERS[a].as_vec3().lerp(CORNERS[b].as_vec3(), t);
+            crossings += 1.0;
+        }
+    }
+    (crossings > 0.0).then(|| cell.as_vec3() + sum / crossings)
+}
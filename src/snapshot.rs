@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+
+use crate::lighting::{LightChannel, MAX_LIGHT};
+use crate::lod::ChunkLod;
+use crate::{world_to_local, BlockType, ChunkData, WorldMap, CHUNK_SIZE};
+
+// Voxels copied from each side; meshers read up to one cell past the chunk,
+// and the coarsest cell is this wide
+const PAD: i32 = 2;
+const SIDE: i32 = CHUNK_SIZE + 2 * PAD;
+
+// A voxel of a loaded neighbor, as the mesher sees it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BorderVoxel {
+    pub block: BlockType,
+    pub density: f32,
+    // Brighter of the sky and block light
+    pub light: u8,
+}
+
+// The layers of the surrounding chunks a chunk's mesh depends on, copied out
+// of the `WorldMap` so meshing can run on a worker thread without borrowing
+// it. Covers the faces, edges and corners alike, since the smooth mesher
+// reaches diagonally across seams. Positions are chunk-local, like the
+// mesher's; those of unloaded neighbors are `None`, for the caller to fall
+// back on the generator.
+#[derive(Clone)]
+pub struct NeighborSnapshot {
+    // Padded cube around the chunk; entries inside the chunk itself stay `None`
+    voxels: Vec<Option<BorderVoxel>>,
+}
+
+// Nothing loaded around the chunk
+impl Default for NeighborSnapshot {
+    fn default() -> Self {
+        Self { voxels: vec![None; (SIDE * SIDE * SIDE) as usize] }
+    }
+}
+
+impl NeighborSnapshot {
+    pub fn capture(world_map: &WorldMap, position: IVec3) -> Self {
+        debug_assert!(ChunkLod::Half.step() <= PAD);
+        let mut snapshot = Self::default();
+        // Each of the 27 chunks around and including this one is looked up once
+        let neighbors: Vec<Option<&ChunkData>> = (0..27)
+            .map(|index| IVec3::new(index % 3, index / 3 % 3, index / 9) - IVec3::ONE)
+            .map(|offset| (offset != IVec3::ZERO).then(|| world_map.chunks.get(&(position + offset))).flatten())
+            .collect();
+
+        let inside = |value: i32| (0..CHUNK_SIZE).contains(&value);
+        for z in -PAD..CHUNK_SIZE + PAD {
+            for y in -PAD..CHUNK_SIZE + PAD {
+                let through_chunk = inside(y) && inside(z);
+                for x in -PAD..CHUNK_SIZE + PAD {
+                    if through_chunk && inside(x) {
+                        continue;
+                    }
+                    let local = IVec3::new(x, y, z);
+                    // Relative to this chunk, so the neighbor comes out as an offset of -1, 0 or 1
+                    let (neighbor, inner) = world_to_local(local);
+                    let offset = neighbor + IVec3::ONE;
+                    let Some(chunk) = neighbors[(offset.x + 3 * (offset.y + 3 * offset.z)) as usize] else {
+                        continue;
+                    };
+                    snapshot.voxels[padded_index(local)] = Some(BorderVoxel {
+                        block: chunk.get(inner),
+                        density: chunk.density(inner),
+                        light: chunk.light(inner, LightChannel::Sky).max(chunk.light(inner, LightChannel::Block)),
+                    });
+                }
+            }
+        }
+        snapshot
+    }
+
+    // The neighbor voxel at a chunk-local position at most `PAD` outside the chunk
+    pub fn get(&self, local: IVec3) -> Option<BorderVoxel> {
+        self.voxels[padded_index(local)]
+    }
+
+    // Unloaded neighbors count as fully lit rather than leaving dark seams
+    pub fn light(&self, local: IVec3) -> u8 {
+        self.get(local).map_or(MAX_LIGHT, |voxel| voxel.light)
+    }
+}
+
+fn padded_index(local: IVec3) -> usize {
+    let p = local + IVec3::splat(PAD);
+    (p.x + SIDE * (p.y + SIDE * p.z)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_faces_edges_and_corners_of_loaded_neighbors() {
+        let mut world_map = WorldMap::default();
+        for offset in [IVec3::X, IVec3::new(-1, -1, -1)] {
+            let mut chunk = ChunkData::new();
+            for local in ChunkData::iter_coords() {
+                chunk.set(local, BlockType::Stone);
+            }
+            world_map.chunks.insert(offset, chunk);
+        }
+        let snapshot = NeighborSnapshot::capture(&world_map, IVec3::ZERO);
+
+        let block = |local: IVec3| snapshot.get(local).map(|voxel| voxel.block);
+        assert_eq!(block(IVec3::new(CHUNK_SIZE, 5, 5)), Some(BlockType::Stone));
+        assert_eq!(block(IVec3::new(CHUNK_SIZE + 1, -2, CHUNK_SIZE + 1)), None);
+        assert_eq!(block(IVec3::new(-2, -1, -2)), Some(BlockType::Stone));
+        assert_eq!(block(IVec3::new(-1, 5, 5)), None);
+        assert_eq!(snapshot.light(IVec3::new(-1, 5, 5)), MAX_LIGHT);
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::player::{PlayerPhysics, EYE_HEIGHT};
+use crate::{BlockChanged, BlockType, Player, WorldMap, WorldOrigin};
+
+// Horizontal distance walked between footsteps
+const STEP_DISTANCE: f32 = 1.8;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SoundAssets>()
+            .add_systems(Update, (play_block_sounds, play_footsteps));
+    }
+}
+
+// Blocks that share a set of clips
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SoundGroup {
+    Stone,
+    Dirt,
+    Grass,
+    Wood,
+}
+
+impl SoundGroup {
+    const ALL: [SoundGroup; 4] = [SoundGroup::Stone, SoundGroup::Dirt, SoundGroup::Grass, SoundGroup::Wood];
+
+    fn name(self) -> &'static str {
+        match self {
+            SoundGroup::Stone => "stone",
+            SoundGroup::Dirt => "dirt",
+            SoundGroup::Grass => "grass",
+            SoundGroup::Wood => "wood",
+        }
+    }
+}
+
+fn sound_group(block: BlockType) -> Option<SoundGroup> {
+    match block {
+        BlockType::Stone
+        | BlockType::StoneSlab
+        | BlockType::CoalOre
+        | BlockType::IronOre
+        | BlockType::Bedrock
+        | BlockType::Glowstone
+        | BlockType::Glass => Some(SoundGroup::Stone),
+        BlockType::Dirt | BlockType::Sand => Some(SoundGroup::Dirt),
+        BlockType::Grass | BlockType::Leaves => Some(SoundGroup::Grass),
+        BlockType::Log(_) => Some(SoundGroup::Wood),
+        BlockType::Air | BlockType::Water | BlockType::FlowingWater(_) => None,
+    }
+}
+
+// Clips are loaded from `assets/sounds/<group>_<kind>.ogg`
+#[derive(Resource)]
+pub struct SoundAssets {
+    pub step: HashMap<SoundGroup, Handle<AudioSource>>,
+    pub break_block: HashMap<SoundGroup, Handle<AudioSource>>,
+    pub place_block: HashMap<SoundGroup, Handle<AudioSource>>,
+}
+
+impl FromWorld for SoundAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let load = |kind: &str| {
+            SoundGroup::ALL
+                .into_iter()
+                .map(|group| (group, asset_server.load(format!("sounds/{}_{}.ogg", group.name(), kind))))
+                .collect()
+        };
+        Self {
+            step: load("step"),
+            break_block: load("break"),
+            place_block: load("place"),
+        }
+    }
+}
+
+fn play_clip(commands: &mut Commands, clips: &HashMap<SoundGroup, Handle<AudioSource>>, block: BlockType) {
+    if let Some(source) = sound_group(block).and_then(|group| clips.get(&group)) {
+        commands.spawn(AudioBundle {
+            source: source.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+fn play_block_sounds(mut commands: Commands, mut events: EventReader<BlockChanged>, sounds: Res<SoundAssets>) {
+    for event in events.read() {
+        if event.new == BlockType::Air {
+            play_clip(&mut commands, &sounds.break_block, event.old);
+        } else {
+            play_clip(&mut commands, &sounds.place_block, event.new);
+        }
+    }
+}
+
+fn play_footsteps(
+    mut commands: Commands,
+    time: Res<Time>,
+    sounds: Res<SoundAssets>,
+    world_map: Res<WorldMap>,
+    world_origin: Res<WorldOrigin>,
+    mut walked: Local<f32>,
+    query: Query<(&Transform, &PlayerPhysics), With<Player>>,
+) {
+    let Ok((transform, physics)) = query.get_single() else {
+        return;
+    };
+    if !physics.grounded {
+        return;
+    }
+
+    *walked += physics.horizontal_velocity().length() * time.delta_seconds();
+    if *walked < STEP_DISTANCE {
+        return;
+    }
+    *walked = 0.0;
+
+    let feet = transform.translation - physics.up * EYE_HEIGHT * world_origin.voxel_scale;
+    let below = world_origin.voxel_at(feet - physics.up * 0.1 * world_origin.voxel_scale);
+    if let Some(block) = world_map.voxel(below) {
+        play_clip(&mut commands, &sounds.step, block);
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{sample_column, ColumnSamples, WorldGen};
+
+// Chunk columns kept, about 4 KiB each; comfortably more than the columns in
+// range at the largest render distance the menu offers
+const SURFACE_CACHE_COLUMNS: usize = 1024;
+
+// Surface samples of recently generated chunk columns, shared with the
+// generation tasks, so a column that reloads or is generated again doesn't
+// re-evaluate its noise. The least recently used column is dropped once
+// the cache is full.
+#[derive(Resource, Clone, Default)]
+pub struct SurfaceCache {
+    columns: Arc<Mutex<CachedColumns>>,
+}
+
+#[derive(Default)]
+struct CachedColumns {
+    columns: HashMap<IVec2, (Arc<ColumnSamples>, u64)>,
+    // Bumped on every lookup, stamping the columns used
+    clock: u64,
+}
+
+impl SurfaceCache {
+    // The samples of a chunk column, sampled on a miss
+    pub fn column(&self, world_gen: &WorldGen, column: IVec2) -> Arc<ColumnSamples> {
+        {
+            let mut cache = self.lock();
+            cache.clock += 1;
+            let clock = cache.clock;
+            if let Some((samples, used)) = cache.columns.get_mut(&column) {
+                *used = clock;
+                return samples.clone();
+            }
+        }
+
+        // Sampled outside the lock so other tasks aren't held up
+        let samples = Arc::new(sample_column(world_gen, column));
+        let mut cache = self.lock();
+        if cache.columns.len() >= SURFACE_CACHE_COLUMNS {
+            let oldest = cache.columns.iter().min_by_key(|(_, (_, used))| *used).map(|(&column, _)| column);
+            if let Some(oldest) = oldest {
+                cache.columns.remove(&oldest);
+            }
+        }
+        let clock = cache.clock;
+        cache.columns.insert(column, (samples.clone(), clock));
+        samples
+    }
+
+    pub fn clear(&self) {
+        self.lock().columns.clear();
+    }
+
+    // A generation task that panicked mid-update can at worst leave a stale
+    // stamp or a missing column behind, both fine for samples that can be
+    // recomputed, so the lock is taken even once poisoned
+    fn lock(&self) -> MutexGuard<'_, CachedColumns> {
+        self.columns.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Cached samples only hold for the generator that made them
+pub fn clear_surface_cache_on_change(world_gen: Res<WorldGen>, surface_cache: Res<SurfaceCache>) {
+    if world_gen.is_changed() {
+        surface_cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_panicking_task_doesnt_break_the_cache() {
+        let (cache, world_gen) = (SurfaceCache::default(), WorldGen::new(0));
+        let poisoner = cache.clone();
+        let panicked = std::thread::spawn(move || {
+            let _held = poisoner.columns.lock();
+            panic!("generation task failed");
+        })
+        .join();
+        assert!(panicked.is_err() && cache.columns.is_poisoned());
+
+        let samples = cache.column(&world_gen, IVec2::ZERO);
+        assert!(Arc::ptr_eq(&samples, &cache.column(&world_gen, IVec2::ZERO)));
+        cache.clear();
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use noise::{NoiseFn, OpenSimplex, Perlin, Simplex, Value};
+use serde::{Deserialize, Serialize};
+
+// A 2D noise field shared by every clone of `WorldGen`
+pub type Noise2d = Arc<dyn NoiseFn<f64, 2> + Send + Sync>;
+
+// Which gradient noise shapes the terrain and climate. Perlin shows faint
+// grid-aligned ridges; the simplex variants avoid them, and value noise is
+// blockier. Each is fully determined by the seed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum NoiseKind {
+    #[default]
+    Perlin,
+    Simplex,
+    OpenSimplex,
+    Value,
+}
+
+impl NoiseKind {
+    pub fn build(self, seed: u32) -> Noise2d {
+        match self {
+            NoiseKind::Perlin => Arc::new(Perlin::new(seed)),
+            NoiseKind::Simplex => Arc::new(Simplex::new(seed)),
+            NoiseKind::OpenSimplex => Arc::new(OpenSimplex::new(seed)),
+            NoiseKind::Value => Arc::new(Value::new(seed)),
+        }
+    }
+}
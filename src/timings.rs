@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bevy::log::{debug, debug_span};
+use bevy::prelude::*;
+use bevy::utils::tracing::{enabled, Level};
+
+const TIMING_WINDOW: usize = 64;
+
+// Rolling averages of chunk generation and meshing times, for diagnosing stalls
+#[derive(Resource, Default)]
+pub struct ChunkTimings {
+    pub generation: RollingAverage,
+    pub meshing: RollingAverage,
+    // Chunks rebuilt after edits, lighting or LOD changes since startup
+    pub remeshes: u64,
+}
+
+#[derive(Default)]
+pub struct RollingAverage {
+    samples: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl RollingAverage {
+    // Adds a sample and returns the mean of the last `TIMING_WINDOW` samples
+    pub fn push(&mut self, sample: Duration) -> Duration {
+        self.samples.push_back(sample);
+        self.total += sample;
+        if self.samples.len() > TIMING_WINDOW {
+            self.total -= self.samples.pop_front().unwrap_or_default();
+        }
+        self.total / self.samples.len() as u32
+    }
+}
+
+// Runs `f` inside a debug span and logs how long it took. Nothing is measured
+// unless debug logging is enabled.
+pub fn timed<T>(average: &mut RollingAverage, stage: &'static str, position: IVec3, f: impl FnOnce() -> T) -> T {
+    if !enabled!(Level::DEBUG) {
+        return f();
+    }
+
+    let _span = debug_span!("chunk", stage, ?position).entered();
+    let start = Instant::now();
+    let result = f();
+    record(average, stage, position, start.elapsed());
+
+    result
+}
+
+// Logs a time measured elsewhere, e.g. by a background task
+pub fn record(average: &mut RollingAverage, stage: &'static str, position: IVec3, elapsed: Duration) {
+    if enabled!(Level::DEBUG) {
+        let average = average.push(elapsed);
+        debug!(?position, ?elapsed, ?average, "{stage} chunk");
+    }
+}
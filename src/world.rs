@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::{BlockType, WorldMap, WorldOrigin};
+
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub voxel: IVec3,
+    // Face of `voxel` the ray entered through, zero if it started inside it
+    pub normal: IVec3,
+    pub block: BlockType,
+    // How far along the ray it entered `voxel`, in the units of the cast
+    pub distance: f32,
+}
+
+// Walks the voxel grid along the ray (Amanatides & Woo) and returns the first
+// solid voxel within `max_dist`, passing over the open part of a slab. Unloaded chunks are treated as empty. The
+// origin and distance are in absolute blocks, so any system can cast one,
+// whether or not it knows about the player or the render origin.
+pub fn raycast_voxels(world_map: &WorldMap, origin: Vec3, dir: Vec3, max_dist: f32) -> Option<RayHit> {
+    raycast_from(world_map, IVec3::ZERO, origin, dir, max_dist)
+}
+
+// The same cast from a render-space origin, traced relative to the render
+// origin so rays stay precise far from the world's center
+pub fn raycast_render(
+    world_map: &WorldMap,
+    world_origin: &WorldOrigin,
+    origin: Vec3,
+    dir: Vec3,
+    max_dist: f32,
+) -> Option<RayHit> {
+    let origin = world_origin.to_voxel_space(origin);
+    let max_dist = max_dist / world_origin.voxel_scale;
+    let hit = raycast_from(world_map, world_origin.block_offset(), origin, dir, max_dist)?;
+    Some(RayHit { distance: hit.distance * world_origin.voxel_scale, ..hit })
+}
+
+// Traces from `origin` blocks past the voxel `base`
+fn raycast_from(world_map: &WorldMap, base: IVec3, origin: Vec3, dir: Vec3, max_dist: f32) -> Option<RayHit> {
+    let dir = dir.normalize_or_zero();
+    if dir == Vec3::ZERO {
+        return None;
+    }
+
+    let mut voxel = origin.floor().as_ivec3();
+    let moving = dir.cmpne(Vec3::ZERO);
+    let step = Vec3::select(moving, dir.signum(), Vec3::ZERO).as_ivec3();
+    let t_delta = dir.abs().recip();
+    let next_boundary = voxel.as_vec3() + step.max(IVec3::ZERO).as_vec3();
+    let mut t_max = Vec3::select(moving, (next_boundary - origin) / dir, Vec3::splat(f32::INFINITY));
+    let mut normal = IVec3::ZERO;
+    let mut distance = 0.0;
+
+    loop {
+        let world_voxel = voxel + base;
+        if let Some(block) = world_map.voxel(world_voxel) {
+            if block.is_solid() && block.fills_voxel() {
+                return Some(RayHit { voxel: world_voxel, normal, block, distance });
+            }
+            // Shorter blocks are only hit where the ray meets their shape
+            let corner = voxel.as_vec3();
+            let entry = block
+                .collision_shape()
+                .iter()
+                .filter_map(|part| part.ray_entry(corner, origin, dir))
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((distance, axis)) = entry.filter(|(distance, _)| *distance <= max_dist) {
+                let mut normal = IVec3::ZERO;
+                if let Some(axis) = axis {
+                    normal[axis] = -step[axis];
+                }
+                return Some(RayHit { voxel: world_voxel, normal, block, distance });
+            }
+        }
+
+        let axis = if t_max.x < t_max.y && t_max.x < t_max.z {
+            0
+        } else if t_max.y < t_max.z {
+            1
+        } else {
+            2
+        };
+        if t_max[axis] > max_dist {
+            return None;
+        }
+
+        voxel[axis] += step[axis];
+        normal = IVec3::ZERO;
+        normal[axis] = -step[axis];
+        distance = t_max[axis];
+        t_max[axis] += t_delta[axis];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkData;
+
+    // A world holding only the given stone blocks, all inside chunk zero
+    fn world_with(blocks: &[IVec3]) -> WorldMap {
+        let mut chunk = ChunkData::new();
+        for &block in blocks {
+            chunk.set(block, BlockType::Stone);
+        }
+        let mut world_map = WorldMap::default();
+        world_map.chunks.insert(IVec3::ZERO, chunk);
+        world_map
+    }
+
+    #[test]
+    fn axis_aligned_rays_hit_the_face_they_enter() {
+        let world_map = world_with(&[IVec3::new(5, 2, 2), IVec3::new(2, 0, 2)]);
+        let hit = raycast_voxels(&world_map, Vec3::new(0.5, 2.5, 2.5), Vec3::X, 10.0).unwrap();
+        assert_eq!((hit.voxel, hit.normal), (IVec3::new(5, 2, 2), IVec3::NEG_X));
+        let hit = raycast_voxels(&world_map, Vec3::new(2.5, 8.5, 2.5), Vec3::NEG_Y, 10.0).unwrap();
+        assert_eq!((hit.voxel, hit.normal), (IVec3::new(2, 0, 2), IVec3::Y));
+        assert_eq!(hit.block, BlockType::Stone);
+        // Entered after 4.5 blocks, so just out of reach at 4
+        assert!(raycast_voxels(&world_map, Vec3::new(0.5, 2.5, 2.5), Vec3::X, 4.0).is_none());
+    }
+
+    #[test]
+    fn hits_know_how_far_along_the_ray_they_are() {
+        let world_map = world_with(&[IVec3::new(5, 2, 2)]);
+        let hit = raycast_voxels(&world_map, Vec3::new(0.5, 2.5, 2.5), Vec3::X, 10.0).unwrap();
+        assert_eq!(hit.distance, 4.5);
+        // Measured in render units when cast from render space
+        let origin = WorldOrigin { chunk: IVec3::ZERO, voxel_scale: 0.5 };
+        let hit = raycast_render(&world_map, &origin, Vec3::new(0.25, 1.25, 1.25), Vec3::X, 5.0).unwrap();
+        assert_eq!(hit.distance, 2.25);
+        // Starting inside a block hits it right away
+        let hit = raycast_voxels(&world_map, Vec3::new(5.5, 2.5, 2.5), Vec3::NEG_X, 10.0).unwrap();
+        assert_eq!(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn diagonal_rays_step_through_every_crossed_voxel() {
+        let world_map = world_with(&[IVec3::new(4, 4, 0)]);
+        let hit = raycast_voxels(&world_map, Vec3::new(0.5, 0.4, 0.5), Vec3::new(1.0, 1.0, 0.0), 10.0).unwrap();
+        assert_eq!(hit.voxel, IVec3::new(4, 4, 0));
+        // Starting a little below the diagonal, it crosses into the block's row last
+        assert_eq!(hit.normal, IVec3::NEG_Y);
+        // A ray just beside the block misses it
+        assert!(raycast_voxels(&world_map, Vec3::new(0.5, 1.5, 0.5), Vec3::new(1.0, 1.0, 0.0), 10.0).is_none());
+    }
+
+    #[test]
+    fn rays_only_hit_the_lower_half_of_a_slab() {
+        let mut world_map = world_with(&[]);
+        world_map.chunks.get_mut(&IVec3::ZERO).unwrap().set(IVec3::new(5, 2, 2), BlockType::StoneSlab);
+        // Over its top half the ray passes on to whatever lies beyond
+        assert!(raycast_voxels(&world_map, Vec3::new(0.5, 2.75, 2.5), Vec3::X, 10.0).is_none());
+        let hit = raycast_voxels(&world_map, Vec3::new(0.5, 2.25, 2.5), Vec3::X, 10.0).unwrap();
+        assert_eq!((hit.voxel, hit.normal, hit.distance), (IVec3::new(5, 2, 2), IVec3::NEG_X, 4.5));
+        // From above it's hit on its top, half a block into the voxel
+        let hit = raycast_voxels(&world_map, Vec3::new(5.5, 6.0, 2.5), Vec3::NEG_Y, 10.0).unwrap();
+        assert_eq!((hit.voxel, hit.normal, hit.distance), (IVec3::new(5, 2, 2), IVec3::Y, 3.5));
+    }
+
+    #[test]
+    fn rays_starting_inside_a_solid_voxel_hit_it() {
+        let world_map = world_with(&[IVec3::new(3, 3, 3), IVec3::new(4, 3, 3)]);
+        let hit = raycast_voxels(&world_map, Vec3::new(3.5, 3.5, 3.5), Vec3::X, 10.0).unwrap();
+        assert_eq!((hit.voxel, hit.normal), (IVec3::new(3, 3, 3), IVec3::ZERO));
+    }
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "headless")]
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use voxel_world2::{
+    chunk_to_world_origin, GameState, HeadlessPlugin, Player, PlayerPlugin, Settings, VoxelWorldPlugin, WorldGen,
+    WorldMap, WorldOrigin, EYE_HEIGHT,
+};
+
+// The whole game without a window or GPU: chunks stream in around the spawn
+// until loading hands control to the player
+#[test]
+fn headless_world_loads_and_starts_playing() {
+    let app = playing_app();
+    assert!(!app.world.resource::<WorldMap>().is_empty());
+}
+
+// A player taken to a column far outside what loaded at the spawn waits
+// there until its chunks generate, then lands on the ground
+#[test]
+fn terrain_generates_around_a_player_who_moved_away() {
+    let mut app = playing_app();
+    let column = IVec2::new(app.world.resource::<Settings>().render_distance + 4, 0);
+    let corner = chunk_to_world_origin(IVec3::new(column.x, 0, column.y));
+    let (x, z) = (corner.x + 8, corner.z + 8);
+    let ground = app.world.resource::<WorldGen>().surface_height_at(x, z) as f32;
+    let world_origin = app.world.resource::<WorldOrigin>();
+    let feet = Vec3::new(x as f32 + 0.5, ground + 2.0, z as f32 + 0.5);
+    let eye = (feet + Vec3::Y * EYE_HEIGHT - world_origin.block_offset().as_vec3()) * world_origin.voxel_scale;
+    let mut player_query = app.world.query_filtered::<&mut Transform, With<Player>>();
+    player_query.single_mut(&mut app.world).translation = eye;
+
+    let chunks: Vec<IVec3> =
+        app.world.resource::<WorldGen>().chunk_y_range().map(|y| IVec3::new(column.x, y, column.y)).collect();
+    assert!(chunks.iter().all(|&chunk| !app.world.resource::<WorldMap>().contains(chunk)));
+    update_until(&mut app, "the column to generate", |app| {
+        chunks.iter().all(|&chunk| app.world.resource::<WorldMap>().contains(chunk))
+    });
+    // Long enough to fall the two blocks, and to fall through if it were going to
+    let settled = Instant::now() + Duration::from_secs(2);
+    update_until(&mut app, "the player to settle", |_| Instant::now() > settled);
+
+    let eye = player_query.single(&app.world).translation;
+    let feet = app.world.resource::<WorldOrigin>().to_world(eye).y - EYE_HEIGHT;
+    assert!((ground - 0.01..ground + 2.01).contains(&feet), "feet at {feet}, ground at {ground}");
+}
+
+// Runs the game until loading hands control to the player
+fn playing_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((HeadlessPlugin, VoxelWorldPlugin::default(), PlayerPlugin));
+    update_until(&mut app, "loading", |app| *app.world.resource::<State<GameState>>().get() == GameState::Playing);
+    app
+}
+
+fn update_until(app: &mut App, waiting_for: &str, done: impl Fn(&App) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while !done(app) {
+        assert!(Instant::now() < deadline, "still waiting for {waiting_for} after a minute");
+        app.update();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}